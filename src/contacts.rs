@@ -0,0 +1,54 @@
+use std::*;
+use rayon::prelude::*;
+
+use crate::screen::{self, Rgb};
+use crate::three::Point;
+
+// Residue–residue distances binned onto a square of `size` pixels, keeping
+// the closest CA pair in each bin so no contact is lost when downsampling.
+pub struct ContactMap {
+    pub size: u16,
+    distances: Vec<f32>,
+}
+
+impl ContactMap {
+    pub fn new(residues: &[Point], size: u16) -> ContactMap {
+        let count = residues.len();
+        let pixels = size as usize;
+        // Residues in a pixel row or column; small structures repeat residues.
+        let bin = |pixel: usize| {
+            let start = pixel * count / pixels;
+            start..((pixel + 1) * count / pixels).max(start + 1).min(count)
+        };
+
+        let mut distances = vec![f32::INFINITY; pixels * pixels];
+        if count > 0 {
+            distances.par_chunks_mut(pixels).enumerate().for_each(|(row, cells)| {
+                for (column, cell) in cells.iter_mut().enumerate() {
+                    for i in bin(row) {
+                        for j in bin(column) {
+                            *cell = cell.min(residues[i].distance(&residues[j]));
+                        }
+                    }
+                }
+            });
+        }
+
+        ContactMap { size, distances }
+    }
+
+    // Plot pairs closer than `threshold` Å with the left edge at pixel
+    // `x_offset`, nearer pairs toward the top of the gradient.
+    pub fn plot<F>(&self, screen: &mut screen::Screen, x_offset: u16, threshold: f32, color_fn: F)
+    where
+        F: Fn(f32) -> Rgb,
+    {
+        let pixels = self.size as usize;
+        for (i, distance) in self.distances.iter().enumerate() {
+            if *distance < threshold {
+                let point = screen::Point::new((x_offset as usize + i % pixels) as i32, (i / pixels) as i32);
+                screen.write_color(true, &point, color_fn(1. - distance / threshold), 0.);
+            }
+        }
+    }
+}
@@ -2,7 +2,7 @@
 // Based on terminal3d by Liam Ilan (https://github.com/liam-ilan/terminal3d)
 
 use std::*;
-use std::io::Write;
+use std::io::{Read, Write};
 use process::exit;
 use time::Duration;
 
@@ -13,18 +13,73 @@ use crossterm::{
     cursor
 };
 
+// eprintln! for progress messages, which --quiet silences.
+macro_rules! progress {
+    ($($arg:tt)*) => {
+        if crate::model::verbosity() > crate::model::Verbosity::Quiet {
+            eprintln!($($arg)*);
+        }
+    };
+}
+
 mod screen;
 mod three;
 mod model;
+mod svg;
+mod obj;
+mod contacts;
 
 const VIEWPORT_FOV: f32 = 1.7;
+const FOV_RANGE: (f32, f32) = (0.5, 2.5); // radians
+const FOV_STEP: f32 = 0.05; // radians per key press
 const VIEWPORT_DISTANCE: f32 = 0.1;
-const TARGET_DURATION_PER_FRAME: Duration = Duration::from_millis(1000 / 30); // 30 FPS target
+const DEFAULT_FPS: f32 = 30.; // target frames per second
+const IDLE_POLL_MS: u64 = 250; // input wait while nothing on screen changes
 const MOUSE_SPEED_MULTIPLIER: f32 = 30.;
 const INITIAL_DISTANCE_MULTIPLIER: f32 = 1.2;
+const INITIAL_ORIENTATION: (f32, f32) = (0.3, 0.2); // yaw and pitch in radians, unless --rotate-to
+const FIT_FILL: f32 = 0.9; // fraction of the screen a structure framed on load may span
 const SCROLL_MULTIPLER: f32 = 0.03;
+const ZOOM_PRESETS: [f32; 3] = [1., 0.6, 0.35]; // keys 1-3: fit, close-up, tight, as fractions of the initial distance
+const TRANSITION_TIME: Duration = Duration::from_millis(300); // camera glide on reset and zoom presets
 const PAN_MULTIPLIER: f32 = 0.1;
 const AUTO_ROTATE_SPEED: f32 = 0.002; // radians per frame (slower rotation)
+const SPIN_SPEED_RANGE: (f32, f32) = (0.125, 16.); // auto-rotate speed multipliers
+const SPIN_SPEED_STEP: f32 = 1.25; // multiplier change per key press
+const MOMENTUM_DAMPING: f32 = 0.9; // per-frame decay of rotation after a flick
+const MOMENTUM_THRESHOLD: f32 = 0.0005; // radians per frame below which momentum stops
+const ROLL_STEP: f32 = 0.05; // radians per key press
+const BRIGHTNESS_RANGE: (f32, f32) = (0.2, 3.0); // color multipliers
+const BRIGHTNESS_STEP: f32 = 0.1; // multiplier change per key press
+const DEFAULT_ENSEMBLE_RATE: f32 = 5.; // states per second
+const DEFAULT_RENDER_SIZE: (u16, u16) = (80, 24); // characters, for --render without a terminal
+const MIN_VIEWPORT_CELLS: (u16, u16) = (12, 4); // columns and rows each side-by-side or stacked structure needs
+const DEFAULT_BENCH_FRAMES: usize = 200; // frames timed by `pepterm bench`
+const SVG_CANVAS_SIZE: (u32, u32) = (1200, 900); // pixels, for --svg
+const LEGEND_WIDTH: usize = 24; // gradient cells in the color legend
+const SWATCH_WIDTH: usize = 32; // half-block cells per gradient in `pepterm colors`
+const AXIS_LENGTH: f32 = 0.15; // orientation axes, as a fraction of the model diagonal
+const ACTIVE_VIEWPORT_COLOR: screen::Rgb = screen::Rgb { r: 90, g: 90, b: 110 }; // frame around the viewport a drag turns alone
+const SS_MIN_STRUCTURED: f32 = 0.05; // helix and sheet fraction below which --color ss falls back to rainbow
+const CONTACT_THRESHOLD: f32 = 8.; // Å between CA atoms counted as a contact
+const CONTACT_THRESHOLD_RANGE: (f32, f32) = (4., 20.); // Å
+const CONTACT_THRESHOLD_STEP: f32 = 0.5; // Å per key press
+const EDGE_BUDGET_CELLS: f32 = 12000.; // terminal cells the default edge caps suit
+const EDGE_BUDGET_SCALE_RANGE: (f32, f32) = (0.2, 2.);
+const CLIP_LIMIT: f32 = 0.5; // clip plane offset from the center that leaves that side open, in model diagonals
+const CLIP_STEP: f32 = 0.025; // clip plane move per key press, in model diagonals
+const OVERLAY_COLORS: [(u8, u8, u8); 6] = [
+    (255, 140, 0), (0, 170, 255), (120, 220, 80), (230, 80, 200), (255, 225, 60), (235, 235, 235),
+];
+const DEMO_COLOR_INTERVAL: Duration = Duration::from_secs(8); // time on each color scheme in --demo
+const STEREO_SEPARATION: f32 = 1. / 30.; // eye distance as a fraction of the viewing distance
+const PICK_RADIUS: f32 = 6.; // screen pixels (braille dots) to snap a click to a residue
+const PREVIEW_DELAY: Duration = Duration::from_millis(300); // loading time before the edges read so far are drawn
+const HOVER_INTERVAL: Duration = Duration::from_millis(100); // least time between residue lookups under the mouse
+const BACKGROUND_QUERY_TIMEOUT: Duration = Duration::from_millis(150); // wait for the terminal to report its colors
+const BACKGROUND_REPLY_LIMIT: Duration = Duration::from_secs(1); // wait for the rest of a reply once it has started
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+const MAX_SEARCH_LIMIT: usize = 100; // each result triggers a title lookup
 
 const HELP_MSG: &str = "\
 \x1b[1mpepterm\x1b[0m: View protein structures in your terminal!
@@ -32,16 +87,134 @@ const HELP_MSG: &str = "\
 \x1b[1mUsage\x1b[0m:
     pepterm <PDB_ID>                   Fetch and view protein from RCSB PDB
     pepterm <PDB_ID> <PDB_ID> ...      View multiple structures side-by-side
+    pepterm <ID> <ID> ... --overlay    Superimpose multiple structures in one view
+    pepterm af:<UNIPROT>               Fetch and view an AlphaFold DB prediction
     pepterm <file.pdb|.cif>            View local PDB/CIF file
+    pepterm <https://…/file.pdb>       Download and view a structure file
+    pepterm <file.mol2|.sdf>           View a small molecule as sticks
+    pepterm <file.xyz>                 View atoms from an XYZ file (no PyMOL needed)
     pepterm <file.obj>                 View OBJ file
     pepterm <ID> --chain <CHAIN>       Show specific chain only
+    pepterm prefetch <ID> <ID> ...     Fetch and cache structures without viewing
+    pepterm prefetch @<list.txt>       Prefetch the IDs listed in a file
     pepterm search <QUERY>             Search RCSB PDB
+    pepterm search <QUERY> --limit <N> Show up to N results (max 100)
+    pepterm search <QUERY> --json      Print the results as a JSON array
+    pepterm info <PDB_ID>              Show structure metadata
+    pepterm chains <ID|file>           Print chain IDs, one per line
+    pepterm colors                     Preview every color scheme
     pepterm cache                      Show cache info
+    pepterm cache list                 List cached files, largest first
     pepterm cache clear                Clear cached files
+    pepterm cache clear <PDB_ID>       Clear cached files for one entry (accepts * and ? globs)
 
 \x1b[1mOptions\x1b[0m:
-    --chain, -n <CHAIN>   Show only the specified chain (e.g., A, B)
+    --chain, -n <CHAIN>   Show only the specified chain (e.g., A, B; case-sensitive)
+    --select <SEL>        Show only atoms matching a PyMOL selection
+                          (e.g., \"resi 1-50 and chain A\")
+    --source <DB>         Where bare IDs come from: pdb (default) or alphafold
+    --assembly <FORM>     For PDB IDs: asym (asymmetric unit, default) or bio1
+                          (first biological assembly; even when asked for,
+                          falls back to asym above 300000 atoms)
+    --list-chains         Print the chain IDs and exit (same as pepterm chains)
+    --pick-chain          List the chains and choose one from a menu
+    --center <CHAIN/RESI> Orbit around a residue's CA atom (e.g., A/57)
     --color, -c <SCHEME>  Specify color scheme
+    --palette, -p <STOPS> Custom gradient as r,g,b:r,g,b:... (at least two stops)
+    --highlight <C/S-E>   Color residues S-E of chain C, dim the rest (repeatable)
+    --label <C/R:TEXT>    Write TEXT beside residue R of chain C, following it
+                          as the model turns (repeatable; single or overlay view)
+    --mono <HEX>          Draw everything in one color (e.g., #FF8800)
+    --reverse, -r         Reverse the color gradient direction
+    --no-cache            Re-fetch and regenerate instead of using cached files
+    --cache-ttl <DAYS>    Re-fetch downloaded entries cached more than DAYS days
+                          ago (default: keep them forever)
+    --timeout <SECS>      Give up on downloads and PyMOL runs after SECS
+                          seconds (default 60; also for search and info)
+    --quiet               Print only warnings and errors, not progress such as
+                          fetching, cache and retry messages (also for search;
+                          no short form, as -q is --quality)
+    --verbose             Also print each generated PyMOL script (also
+                          accepted by search)
+    --quality, -q <LEVEL> Cartoon detail: low, medium (default) or high.
+                          Higher levels look smoother but take longer to
+                          generate and render more slowly on large structures
+    --repr <REPR>         Representation: cartoon (default), surface, sticks, ribbon,
+                          backbone, or trace/tube (CA trace drawn without PyMOL)
+    --max-edges <N>       Keep at most N edges, dropping the shortest first
+                          (default scales with the terminal size)
+    --backbone            Same as --repr backbone: thin PyMOL cylinders between
+                          CA atoms, a sparse view of huge assemblies
+    --disulfides          Draw disulfide bonds as bright yellow sticks (not
+                          with trace/tube)
+    --hbonds              Draw hydrogen bonds (polar contacts) as dashed cyan
+                          lines (not with trace/tube)
+    --smooth, -s          Antialias lines for smoother cartoons
+    --ribbon              Shade each line with a strand on either side for a
+                          rounder, tube-like look (widens with the zoom)
+    --alpha <0..1>        Line opacity: draw only this fraction of each line's
+                          pixels so overlapping layers show through
+    --thickness <1|2>     Line width in pixels; 2 draws bolder lines
+                          (ignored with --smooth)
+    --split <DIR>         Lay out several structures horizontal (side by side,
+                          default) or vertical (stacked, for tall terminals);
+                          each needs 12 columns or 4 rows
+    --stereo <MODE>       3D viewing: sbs (side-by-side pair for parallel
+                          free-viewing) or anaglyph (red/cyan glasses)
+    --contacts            Show a CA contact map beside the model
+    --fov <DEG>           Initial field of view in degrees (29-143, default 97)
+    --rotate-to <Y,P>     Initial yaw and pitch in degrees, also restored by the
+                          0 key (alias --orient)
+    --demo                Kiosk mode: orbit and switch color schemes every 8
+                          seconds, ignoring all input but quit
+    --no-rotate           Start with auto-rotation off (--rotate forces it on)
+    --spin-axis <AXIS>    Auto-rotate around yaw (default), pitch or both
+    --spin-speed <F>      Auto-rotate speed multiplier (default 1)
+    --no-transitions      Snap the camera on reset and zoom presets instead
+                          of gliding there
+    --pixel <TYPE>        Character cells: braille (default) or block
+    --colors <DEPTH>      Color output: truecolor or 256 (default: truecolor
+                          when $COLORTERM says so, 256 otherwise; always
+                          truecolor for --render)
+    --cvd <TYPE>          Preview colors as seen with protanopia, deuteranopia
+                          or tritanopia
+    --fps <N>             Target frame rate (default 30)
+    --bg <HEX>            Paint a background color (e.g., #101018); without it,
+                          edges fade toward the terminal's own background
+                          when the terminal reports it (not asked over
+                          ssh), else toward black
+    --invert              Negative image: fill the empty space (in the --bg
+                          color, or white) and leave the structure blank
+    --region <X,Y,W,H>    Draw only in the W x H character rectangle at column X,
+                          row Y (from 0), leaving the rest of the terminal to
+                          other programs; the status bar is its last row
+    --transparent-bg      Skip empty cells instead of drawing spaces, so the
+                          terminal's own content shows through (for recorders
+                          and overlays; overrides --bg)
+    --zoom-speed <F>      Scroll zoom speed multiplier (default 1, or $PEPTERM_ZOOM_SPEED)
+    --invert-scroll       Reverse scroll zoom direction (or PEPTERM_INVERT_SCROLL=1)
+    --render <FILE>       Render one frame to FILE and exit (.txt = plain text,
+                          anything else = ANSI color)
+    --size <WxH>          Output size in characters for --render (e.g., 100x40)
+    --svg <FILE>          Write the projected lines as an SVG image and exit
+                          (not with --render)
+    --dump-obj <FILE>     Write the rendered line geometry (after decimation)
+                          as an OBJ with vertex colors; exits if combined
+                          with --render or --svg
+    --perf                Show frame timings (events, plotting, terminal output),
+                          edge and cell counts in the status bar
+    --overlay             Superimpose several structures in one view, each in
+                          its own color, instead of side by side
+    --align               Superpose each structure onto the first with PyMOL
+                          and overlay them (reports the RMSD). Equivalent
+                          residues share a color
+    --ensemble, -e        Animate all states of an NMR/multi-model entry
+                          (or all frames of an XYZ trajectory)
+    --ensemble-rate <N>   Ensemble playback speed in states per second (default 5)
+
+\x1b[1mSearch Options\x1b[0m:
+    --limit, -l <N>       Number of results to show (default 10, max 100)
+    --start <OFFSET>      Skip the first OFFSET results
 
 \x1b[1mColor Schemes\x1b[0m:
     coolwarm     Blue to red diverging (default)
@@ -57,31 +230,82 @@ const HELP_MSG: &str = "\
     inferno      Black to yellow via red
     spectral     Spectral rainbow
     white        White monochrome
+    element      CPK colors by element (sticks, surfaces, XYZ files and OBJ
+                 files with element materials; cartoons stay carbon gray)
+    chain        Distinct color per chain
+    plddt        AlphaFold confidence bands from B-factors (default for af:)
+    ss           Helix, sheet and loop as assigned by PyMOL (falls back to
+                 rainbow when there is almost no helix or sheet)
+    obj          Vertex colors stored in an OBJ file (v x y z r g b)
+
+\x1b[1mConfig\x1b[0m:
+    Defaults can be set in ~/.config/pepterm/config.toml (color, pixel, fps,
+    background, cache_limit_mb, zoom_speed, invert_scroll, smooth, ribbon,
+    quality, rotate, colors, transitions, cvd).
+    Command-line flags override them.
 
 \x1b[1mExamples\x1b[0m:
     pepterm 1CRN                  View crambin protein
     pepterm 4HHB                  View hemoglobin
     pepterm 4HHB --chain A        View only chain A
+    pepterm 1CRN --repr surface   View the molecular surface
+    pepterm 4HHB --select \"chain A and resi 1-50\"
+                                  View the first 50 residues of chain A
+    pepterm 1CRN --render 1crn.ansi --size 100x40
+                                  Write a 100x40 thumbnail without a UI
+    pepterm 1CRN --svg 1crn.svg   Export the projection as a vector image
+    pepterm 1CRN --dump-obj 1crn.obj --svg 1crn.svg
+                                  Save the decimated geometry too
+    pepterm 1D3Z --ensemble       Animate the NMR ensemble of ubiquitin
+    pepterm 1AKE 4AKE --overlay   Compare two conformations in one view
+    pepterm 1AKE 4AKE --align
+                                  Superpose them first and print the RMSD
     pepterm 1CRN --color blues    Use blues colormap
+    pepterm 1CRN --palette 0,0,255:255,255,255:255,0,0
+                                  Use a custom blue-white-red gradient
+    pepterm af:P69905             View the AlphaFold model of hemoglobin alpha
+    pepterm https://files.rcsb.org/download/1CRN.pdb
+                                  View a structure file from a URL
     pepterm ./protein.pdb         View local PDB file
     pepterm ./structure.cif       View local CIF file
     pepterm search insulin        Search for insulin structures
+    pepterm info 4HHB             Show hemoglobin metadata
 
 \x1b[1mControls\x1b[0m:
     Mouse drag         Rotate around the model (disables auto-rotate)
     Shift + drag       Pan the view
     Scroll up/down     Zoom in/out
     [r]                Toggle auto-rotation
-    [c]                Cycle through color schemes
-    [0]                Reset view
+    [+ / -]            Speed up/slow down auto-rotation
+    [c]                Cycle through color schemes and any --palette
+                       ([C] goes back)
+    [b]                Toggle the bounding box and X/Y/Z axes
+    [l]                Toggle the color legend in place of the status bar
+    < / >              Dim/brighten colors
+    ( / )              Lower/raise the contact map threshold
+    [ / ]              Roll the view counter-/clockwise
+    { / }              Narrow/widen the field of view
+    [n / N]            Clip away more/less of the front of the model
+    [f / F]            Clip away more/less of the back of the model
+    [1 / 2 / 3]        Zoom to fit / close-up / tight close-up
+    Click or Alt+[1-9] Side by side: drag-rotate only that structure (framed)
+                       until clicked or pressed again
+    [0]                Reset view (including field of view, clipping, spin
+                       speed, auto-rotation and separately turned structures)
+    Click              Pick a residue (CA); a second pick shows the distance (Å)
+    Hover              Show the chain/residue under the pointer in the status bar
+    [x]                Clear picked vertices
+    [space]            Pause/resume ensemble playback
+    [, / .]            Step to the previous/next ensemble state
     [q] or Ctrl+C      Quit
 
 \x1b[1mRequirements\x1b[0m:
-    PyMOL must be installed for cartoon rendering.
+    PyMOL must be installed for cartoon rendering. Without it, PDB/CIF
+    structures are drawn as a CA trace.
     Install via: brew install pymol
 ";
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, PartialEq)]
 pub enum ColorScheme {
     Rainbow,
     Blues,
@@ -96,6 +320,13 @@ pub enum ColorScheme {
     Coolwarm,
     Spectral,
     White,
+    Element,
+    Chain,
+    Plddt,
+    Ss,
+    Obj,
+    Custom(Vec<(u8, u8, u8)>),
+    Mono(u8, u8, u8),
 }
 
 impl ColorScheme {
@@ -114,10 +345,24 @@ impl ColorScheme {
             "coolwarm" => Some(ColorScheme::Coolwarm),
             "spectral" => Some(ColorScheme::Spectral),
             "white" => Some(ColorScheme::White),
+            "element" | "cpk" => Some(ColorScheme::Element),
+            "chain" | "chainbow" => Some(ColorScheme::Chain),
+            "plddt" | "bfactor" => Some(ColorScheme::Plddt),
+            "ss" | "secondary" => Some(ColorScheme::Ss),
+            "obj" | "vertex" => Some(ColorScheme::Obj),
             _ => None,
         }
     }
 
+    // Every named scheme, in the order the help lists them.
+    fn builtin() -> Vec<ColorScheme> {
+        [
+            "coolwarm", "rainbow", "blues", "greens", "reds", "oranges", "purples", "viridis",
+            "plasma", "magma", "inferno", "spectral", "white", "element", "chain", "plddt",
+            "ss", "obj",
+        ].into_iter().filter_map(ColorScheme::from_str).collect()
+    }
+
     fn name(&self) -> &'static str {
         match self {
             ColorScheme::Rainbow => "rainbow",
@@ -133,10 +378,19 @@ impl ColorScheme {
             ColorScheme::Coolwarm => "coolwarm",
             ColorScheme::Spectral => "spectral",
             ColorScheme::White => "white",
+            ColorScheme::Element => "element",
+            ColorScheme::Chain => "chain",
+            ColorScheme::Plddt => "plddt",
+            ColorScheme::Ss => "ss",
+            ColorScheme::Obj => "obj",
+            ColorScheme::Custom(_) => "custom",
+            ColorScheme::Mono(..) => "mono",
         }
     }
 
-    fn next(&self) -> ColorScheme {
+    // Step through the built-in schemes, with the --palette gradient, if one
+    // was given, after the last of them.
+    fn next(&self, palette: Option<&[(u8, u8, u8)]>) -> ColorScheme {
         match self {
             ColorScheme::Rainbow => ColorScheme::Blues,
             ColorScheme::Blues => ColorScheme::Greens,
@@ -150,7 +404,59 @@ impl ColorScheme {
             ColorScheme::Inferno => ColorScheme::Coolwarm,
             ColorScheme::Coolwarm => ColorScheme::Spectral,
             ColorScheme::Spectral => ColorScheme::White,
-            ColorScheme::White => ColorScheme::Rainbow,
+            ColorScheme::White => ColorScheme::Chain,
+            ColorScheme::Chain => palette.map_or(ColorScheme::Rainbow, |stops| ColorScheme::Custom(stops.to_vec())),
+            ColorScheme::Element => ColorScheme::Rainbow,
+            ColorScheme::Plddt => ColorScheme::Rainbow,
+            ColorScheme::Ss => ColorScheme::Rainbow,
+            ColorScheme::Obj => ColorScheme::Rainbow,
+            ColorScheme::Custom(_) => ColorScheme::Rainbow,
+            ColorScheme::Mono(..) => ColorScheme::Rainbow,
+        }
+    }
+
+    // Step back through the same cycle as `next`.
+    fn prev(&self, palette: Option<&[(u8, u8, u8)]>) -> ColorScheme {
+        match self {
+            ColorScheme::Rainbow => palette.map_or(ColorScheme::Chain, |stops| ColorScheme::Custom(stops.to_vec())),
+            ColorScheme::Blues => ColorScheme::Rainbow,
+            ColorScheme::Greens => ColorScheme::Blues,
+            ColorScheme::Reds => ColorScheme::Greens,
+            ColorScheme::Oranges => ColorScheme::Reds,
+            ColorScheme::Purples => ColorScheme::Oranges,
+            ColorScheme::Viridis => ColorScheme::Purples,
+            ColorScheme::Plasma => ColorScheme::Viridis,
+            ColorScheme::Magma => ColorScheme::Plasma,
+            ColorScheme::Inferno => ColorScheme::Magma,
+            ColorScheme::Coolwarm => ColorScheme::Inferno,
+            ColorScheme::Spectral => ColorScheme::Coolwarm,
+            ColorScheme::White => ColorScheme::Spectral,
+            ColorScheme::Chain => ColorScheme::White,
+            ColorScheme::Element => ColorScheme::Chain,
+            ColorScheme::Plddt => ColorScheme::Chain,
+            ColorScheme::Ss => ColorScheme::Chain,
+            ColorScheme::Obj => ColorScheme::Chain,
+            ColorScheme::Custom(_) => ColorScheme::Chain,
+            ColorScheme::Mono(..) => ColorScheme::Chain,
+        }
+    }
+
+    // Name for the status bar, including the color of a mono scheme.
+    fn label(&self) -> String {
+        match self {
+            ColorScheme::Mono(r, g, b) => format!("mono #{:02X}{:02X}{:02X}", r, g, b),
+            _ => self.name().to_string(),
+        }
+    }
+
+    fn color_by(&self) -> model::ColorBy {
+        match self {
+            ColorScheme::Element => model::ColorBy::Element,
+            ColorScheme::Chain => model::ColorBy::Chain,
+            ColorScheme::Plddt => model::ColorBy::Confidence,
+            ColorScheme::Ss => model::ColorBy::SecondaryStructure,
+            ColorScheme::Obj => model::ColorBy::Vertex,
+            _ => model::ColorBy::Position,
         }
     }
 
@@ -170,6 +476,49 @@ impl ColorScheme {
             ColorScheme::Coolwarm => Self::coolwarm(t),
             ColorScheme::Spectral => Self::spectral(t),
             ColorScheme::White => screen::Rgb::new(255, 255, 255),
+            // Untagged geometry (e.g. cartoon) gets the CPK carbon color.
+            ColorScheme::Element => model::Element::Carbon.cpk_color(),
+            // Geometry without chain groups is treated as a single chain.
+            ColorScheme::Chain => model::chain_color("A"),
+            // Geometry without B-factors (e.g. OBJ files) is drawn neutral.
+            ColorScheme::Plddt => screen::Rgb::white(),
+            // Geometry away from any residue (e.g. ligands) is drawn neutral too.
+            ColorScheme::Ss => screen::Rgb::white(),
+            // Vertices without their own color are drawn neutral.
+            ColorScheme::Obj => screen::Rgb::white(),
+            ColorScheme::Custom(colors) => Self::interpolate_palette(colors, t),
+            ColorScheme::Mono(r, g, b) => screen::Rgb::new(*r, *g, *b),
+        }
+    }
+
+    // Parse a palette given as `r,g,b:r,g,b:...` with at least two stops.
+    fn parse_palette(s: &str) -> Result<ColorScheme, String> {
+        let mut colors = Vec::new();
+        for stop in s.split(':') {
+            let components: Vec<&str> = stop.split(',').map(|c| c.trim()).collect();
+            if components.len() != 3 {
+                return Err(format!("Invalid palette stop '{}': expected r,g,b", stop));
+            }
+            let mut rgb = [0u8; 3];
+            for (value, component) in rgb.iter_mut().zip(&components) {
+                *value = component.parse::<u8>().map_err(|_| {
+                    format!("Invalid color component '{}' in '{}': expected 0-255", component, stop)
+                })?;
+            }
+            colors.push((rgb[0], rgb[1], rgb[2]));
+        }
+
+        if colors.len() < 2 {
+            return Err("A palette needs at least two color stops (e.g., 0,0,255:255,0,0)".to_string());
+        }
+        Ok(ColorScheme::Custom(colors))
+    }
+
+    // Parse a flat color given as `RRGGBB` or `#RRGGBB`.
+    fn parse_mono(s: &str) -> Result<ColorScheme, String> {
+        match parse_hex_color(s) {
+            Some(color) => Ok(ColorScheme::Mono(color.r, color.g, color.b)),
+            None => Err(format!("Invalid color '{}': expected a hex color like #FF8800", s)),
         }
     }
 
@@ -299,11 +648,105 @@ impl ColorScheme {
     }
 }
 
+// Parse a color given as `RRGGBB` or `#RRGGBB`.
+fn parse_hex_color(s: &str) -> Option<screen::Rgb> {
+    let hex = s.trim_start_matches('#');
+    let component = |i: usize| hex.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok());
+    match (hex.len(), component(0), component(2), component(4)) {
+        (6, Some(r), Some(g), Some(b)) => Some(screen::Rgb::new(r, g, b)),
+        _ => None,
+    }
+}
+
 fn graceful_close() -> ! {
     cleanup_terminal();
     exit(0)
 }
 
+// Restore the terminal when killed by SIGTERM/SIGHUP (e.g. from `timeout` or
+// a closed window), not only on the handled quit keys.
+#[cfg(unix)]
+fn restore_on_signals() {
+    use signal_hook::consts::{SIGHUP, SIGTERM};
+    let Ok(mut signals) = signal_hook::iterator::Signals::new([SIGTERM, SIGHUP]) else { return };
+    thread::spawn(move || {
+        if let Some(signal) = signals.forever().next() {
+            cleanup_terminal();
+            exit(128 + signal);
+        }
+    });
+}
+
+#[cfg(not(unix))]
+fn restore_on_signals() {}
+
+// Ask the terminal for its background color (OSC 11), so blends toward the
+// background suit light terminals too. Must run in raw mode. The query is
+// followed by a device attributes request, which virtually every terminal
+// answers, so reading stops as soon as the color has gone unreported. Each
+// read waits for input first, so a terminal that answers neither never has
+// the user's first key press taken. A reply that has begun is read to its
+// end, so its tail never reaches the viewer as key presses. Skipped over ssh,
+// where the reply can arrive after any reasonable wait. None (treated as
+// dark) when there is no answer in time.
+#[cfg(unix)]
+fn detect_terminal_background() -> Option<screen::Rgb> {
+    use os::unix::io::AsRawFd;
+
+    if env::var_os("SSH_CONNECTION").is_some() || env::var_os("SSH_TTY").is_some() {
+        return None;
+    }
+    let mut tty = fs::File::open("/dev/tty").ok()?;
+    print!("\x1b]11;?\x07\x1b[c");
+    io::stdout().flush().ok()?;
+
+    let start = time::Instant::now();
+    let mut reply = Vec::new();
+    let mut byte = [0u8];
+    loop {
+        let limit = if reply.is_empty() { BACKGROUND_QUERY_TIMEOUT } else { BACKGROUND_REPLY_LIMIT };
+        let remaining = (start + limit).saturating_duration_since(time::Instant::now());
+        let mut tty_poll = libc::pollfd { fd: tty.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+        // SAFETY: a single valid pollfd, for the duration of the call.
+        let ready = unsafe { libc::poll(&mut tty_poll, 1, remaining.as_millis() as libc::c_int) };
+        if ready <= 0 || tty.read(&mut byte).ok() != Some(1) {
+            break;
+        }
+        reply.push(byte[0]);
+        // The device attributes reply, `ESC [ ? ... c`, comes last.
+        if byte[0] == b'c' && reply.windows(3).any(|w| w == b"\x1b[?") {
+            break;
+        }
+    }
+    parse_background_reply(&String::from_utf8_lossy(&reply))
+}
+
+#[cfg(not(unix))]
+fn detect_terminal_background() -> Option<screen::Rgb> {
+    None
+}
+
+// Color from an OSC 11 reply, `ESC ] 11 ; rgb:RRRR/GGGG/BBBB` ended by BEL or
+// ESC \, with one to four hex digits per channel.
+fn parse_background_reply(reply: &str) -> Option<screen::Rgb> {
+    let start = reply.find("]11;rgb:")? + "]11;rgb:".len();
+    let end = reply[start..].find(['\x07', '\x1b']).map_or(reply.len(), |end| start + end);
+    let channels: Vec<u8> = reply[start..end].split('/')
+        .map(|hex| {
+            let value = u32::from_str_radix(hex, 16).ok().filter(|_| (1..=4).contains(&hex.len()))?;
+            Some((value * 255 / ((1 << (4 * hex.len())) - 1)) as u8)
+        })
+        .collect::<Option<_>>()?;
+    match channels[..] {
+        [r, g, b] => Some(screen::Rgb::new(r, g, b)),
+        _ => None,
+    }
+}
+
+// Whether the viewer switched to the alternate screen, which it leaves on exit.
+// A --region view draws on the screen it was given instead.
+static ALTERNATE_SCREEN: sync::atomic::AtomicBool = sync::atomic::AtomicBool::new(false);
+
 fn cleanup_terminal() {
     let _ = terminal::disable_raw_mode();
     let _ = execute!(
@@ -311,8 +754,10 @@ fn cleanup_terminal() {
         crossterm::style::SetAttribute(crossterm::style::Attribute::Reset),
         cursor::Show,
         event::DisableMouseCapture,
-        terminal::LeaveAlternateScreen,
     );
+    if ALTERNATE_SCREEN.load(sync::atomic::Ordering::SeqCst) {
+        let _ = execute!(io::stdout(), terminal::LeaveAlternateScreen);
+    }
     // Also print reset sequence directly in case execute fails
     print!("\x1b[0m\x1b[?25h");
     let _ = io::stdout().flush();
@@ -324,16 +769,302 @@ fn error_close(msg: &str) -> ! {
 }
 
 enum Command {
-    View(ViewArgs),
-    Search(String),
+    View(Box<ViewArgs>),
+    Search(SearchArgs),
+    Info(String),
+    Chains(String),
+    Colors,
     CacheInfo,
-    CacheClear,
+    CacheList,
+    CacheClear(Option<String>),
+}
+
+struct SearchArgs {
+    query: String,
+    limit: usize,
+    start: usize,
+    // Print the results as a JSON array for scripts.
+    json: bool,
 }
 
 struct ViewArgs {
     inputs: Vec<String>,  // Multiple inputs supported
-    chain: Option<String>,
+    load_options: model::LoadOptions,
     color_scheme: ColorScheme,
+    reverse: bool,
+    ensemble: bool,
+    ensemble_rate: f32,
+    smooth: bool,
+    ribbon: bool,
+    render_path: Option<String>,
+    // Frames to time off screen for `pepterm bench`.
+    bench_frames: Option<usize>,
+    // Only load the inputs into the cache, for `pepterm prefetch`.
+    prefetch: bool,
+    render_size: Option<(u16, u16)>,
+    // Terminal rectangle the viewer draws in, for embedding in other layouts.
+    region: Option<screen::Region>,
+    svg_path: Option<String>,
+    dump_obj_path: Option<String>,
+    zoom_speed: f32,
+    invert_scroll: bool,
+    // Whether auto-rotation is on at start and after a reset.
+    rotate: bool,
+    spin_axis: SpinAxis,
+    spin_speed: f32,
+    // Whether reset and zoom presets glide the camera rather than snap it.
+    transitions: bool,
+    fov: f32,
+    // Initial yaw and pitch in radians.
+    orientation: (f32, f32),
+    highlights: Vec<model::Highlight>,
+    annotations: Vec<model::Annotation>,
+    pixel: PixelMode,
+    // Unset unless --colors or the config chose one; the terminal then
+    // follows $COLORTERM and files get truecolor.
+    color_depth: Option<screen::ColorDepth>,
+    // Color vision deficiency to simulate on output.
+    cvd: Option<screen::Cvd>,
+    // Draw the empty space and leave the structure blank.
+    invert: bool,
+    // Hands-off kiosk mode: orbit and cycle color schemes, only quitting on input.
+    demo: bool,
+    // Line opacity, faked by drawing only this fraction of line pixels.
+    alpha: f32,
+    fps: f32,
+    background: Option<screen::Rgb>,
+    cache_limit_mb: Option<u64>,
+    pick_chain: bool,
+    thickness: u8,
+    stereo: Option<Stereo>,
+    split: Split,
+    contacts: bool,
+    // Leave empty cells unset instead of writing spaces over them.
+    transparent_bg: bool,
+    // Superimpose all inputs in one viewport, one color each.
+    overlay: bool,
+    // Superpose every input onto the first with PyMOL before showing them.
+    align: bool,
+    // Show per-frame timings and scene size in the status bar.
+    perf: bool,
+}
+
+// Axis the view turns around while auto-rotating.
+#[derive(Clone, Copy, PartialEq)]
+enum SpinAxis {
+    Yaw,
+    Pitch,
+    Both,
+}
+
+impl SpinAxis {
+    fn from_str(s: &str) -> Option<SpinAxis> {
+        match s.to_lowercase().as_str() {
+            "yaw" => Some(SpinAxis::Yaw),
+            "pitch" => Some(SpinAxis::Pitch),
+            "both" => Some(SpinAxis::Both),
+            _ => None,
+        }
+    }
+
+    // Advance the orbit by one auto-rotate step.
+    fn spin(&self, orbit: &mut Orbit, speed: f32) {
+        let step = AUTO_ROTATE_SPEED * speed;
+        match self {
+            SpinAxis::Yaw => orbit.yaw += step,
+            SpinAxis::Pitch => orbit.pitch += step,
+            SpinAxis::Both => {
+                orbit.yaw += step;
+                orbit.pitch += step;
+            }
+        }
+    }
+}
+
+// How the two eye views of a stereo pair are shown.
+#[derive(Clone, Copy, PartialEq)]
+enum Stereo {
+    SideBySide,
+    Anaglyph,
+}
+
+impl Stereo {
+    fn from_str(s: &str) -> Option<Stereo> {
+        match s.to_lowercase().as_str() {
+            "sbs" | "side-by-side" => Some(Stereo::SideBySide),
+            "anaglyph" => Some(Stereo::Anaglyph),
+            _ => None,
+        }
+    }
+}
+
+// How the screen is divided between several structures.
+#[derive(Clone, Copy, PartialEq)]
+enum Split {
+    // Side by side, in columns.
+    Horizontal,
+    // Stacked, in rows; better for tall terminals.
+    Vertical,
+}
+
+impl Split {
+    fn from_str(s: &str) -> Option<Split> {
+        match s.to_lowercase().as_str() {
+            "horizontal" | "h" => Some(Split::Horizontal),
+            "vertical" | "v" => Some(Split::Vertical),
+            _ => None,
+        }
+    }
+
+    // Offset and size of viewport `i` of `count` along a canvas, as
+    // (x, y, width, height).
+    fn viewport(&self, i: u16, count: u16, width: u16, height: u16) -> (u16, u16, u16, u16) {
+        match self {
+            Split::Horizontal => (i * (width / count), 0, width / count, height),
+            Split::Vertical => (0, i * (height / count), width, height / count),
+        }
+    }
+}
+
+// Character cell type used to draw the frame.
+#[derive(Clone, Copy, PartialEq)]
+enum PixelMode {
+    Braille,
+    Block,
+}
+
+impl PixelMode {
+    fn from_str(s: &str) -> Option<PixelMode> {
+        match s.to_lowercase().as_str() {
+            "braille" => Some(PixelMode::Braille),
+            "block" => Some(PixelMode::Block),
+            _ => None,
+        }
+    }
+
+    // Subpixels per character cell.
+    fn cell_size(&self) -> (u16, u16) {
+        match self {
+            PixelMode::Braille => (<screen::BrailePixel as screen::Dim>::WIDTH as u16, <screen::BrailePixel as screen::Dim>::HEIGHT as u16),
+            PixelMode::Block => (<screen::BlockPixel as screen::Dim>::WIDTH as u16, <screen::BlockPixel as screen::Dim>::HEIGHT as u16),
+        }
+    }
+
+    fn fit_to_terminal(&self, screen: &mut screen::Screen) {
+        match self {
+            PixelMode::Braille => screen.fit_to_terminal::<screen::BrailePixel>(),
+            PixelMode::Block => screen.fit_to_terminal::<screen::BlockPixel>(),
+        }
+    }
+
+    fn diff_frame(&self, screen: &mut screen::Screen, spans: &[screen::Span]) -> Vec<u8> {
+        match self {
+            PixelMode::Braille => screen.diff_frame::<screen::BrailePixel>(spans),
+            PixelMode::Block => screen.diff_frame::<screen::BlockPixel>(spans),
+        }
+    }
+
+    fn render_diff(&self, screen: &mut screen::Screen, spans: &[screen::Span]) {
+        match self {
+            PixelMode::Braille => screen.render_diff::<screen::BrailePixel>(spans),
+            PixelMode::Block => screen.render_diff::<screen::BlockPixel>(spans),
+        }
+    }
+
+    fn export(&self, screen: &screen::Screen, ansi: bool) -> Vec<u8> {
+        match self {
+            PixelMode::Braille => screen.export::<screen::BrailePixel>(ansi),
+            PixelMode::Block => screen.export::<screen::BlockPixel>(ansi),
+        }
+    }
+}
+
+// Defaults read from the config file; command-line flags override them.
+#[derive(Default)]
+struct Config {
+    color: Option<ColorScheme>,
+    pixel: Option<PixelMode>,
+    fps: Option<f32>,
+    background: Option<screen::Rgb>,
+    cache_limit_mb: Option<u64>,
+    zoom_speed: Option<f32>,
+    invert_scroll: Option<bool>,
+    smooth: Option<bool>,
+    ribbon: Option<bool>,
+    quality: Option<model::Quality>,
+    rotate: Option<bool>,
+    colors: Option<screen::ColorDepth>,
+    transitions: Option<bool>,
+    cvd: Option<screen::Cvd>,
+}
+
+// `$XDG_CONFIG_HOME/pepterm/config.toml`, or `~/.config/pepterm/config.toml`.
+fn config_path() -> Option<path::PathBuf> {
+    let base = env::var("XDG_CONFIG_HOME").map(path::PathBuf::from)
+        .or_else(|_| env::var("HOME").map(|home| path::PathBuf::from(home).join(".config")))
+        .ok()?;
+    Some(base.join("pepterm").join("config.toml"))
+}
+
+// Load the config file if there is one. Problems are reported as warnings and
+// the affected settings keep their built-in defaults.
+fn load_config() -> Config {
+    let mut config = Config::default();
+    let Some(path) = config_path().filter(|p| p.exists()) else { return config };
+
+    let table = match fs::read_to_string(&path).map_err(|e| e.to_string())
+        .and_then(|text| text.parse::<toml::Table>().map_err(|e| e.message().to_string()))
+    {
+        Ok(table) => table,
+        Err(e) => {
+            eprintln!("Warning: ignoring {}: {}", path.display(), e);
+            return config;
+        }
+    };
+
+    for (key, value) in &table {
+        let number = value.as_float().or_else(|| value.as_integer().map(|n| n as f64)).map(|n| n as f32);
+        let valid = match key.as_str() {
+            "color" => value.as_str().and_then(ColorScheme::from_str).map(|v| config.color = Some(v)).is_some(),
+            "pixel" => value.as_str().and_then(PixelMode::from_str).map(|v| config.pixel = Some(v)).is_some(),
+            "fps" => number.filter(|&n| n > 0.).map(|v| config.fps = Some(v)).is_some(),
+            "background" => value.as_str().and_then(parse_hex_color).map(|v| config.background = Some(v)).is_some(),
+            "cache_limit_mb" => value.as_integer().filter(|&n| n > 0).map(|v| config.cache_limit_mb = Some(v as u64)).is_some(),
+            "zoom_speed" => number.filter(|&n| n.is_finite() && n > 0.).map(|v| config.zoom_speed = Some(v)).is_some(),
+            "invert_scroll" => value.as_bool().map(|v| config.invert_scroll = Some(v)).is_some(),
+            "smooth" => value.as_bool().map(|v| config.smooth = Some(v)).is_some(),
+            "ribbon" => value.as_bool().map(|v| config.ribbon = Some(v)).is_some(),
+            "quality" => value.as_str().and_then(model::Quality::from_str).map(|v| config.quality = Some(v)).is_some(),
+            "rotate" => value.as_bool().map(|v| config.rotate = Some(v)).is_some(),
+            "colors" => value.as_str().and_then(screen::ColorDepth::from_str).map(|v| config.colors = Some(v)).is_some(),
+            "transitions" => value.as_bool().map(|v| config.transitions = Some(v)).is_some(),
+            "cvd" => value.as_str().and_then(screen::Cvd::from_str).map(|v| config.cvd = Some(v)).is_some(),
+            _ => {
+                eprintln!("Warning: unknown setting '{}' in {}", key, path.display());
+                continue;
+            }
+        };
+        if !valid {
+            eprintln!("Warning: invalid value for '{}' in {}; using the default", key, path.display());
+        }
+    }
+
+    config
+}
+
+// Default edge cap for a terminal of `cells` characters: small terminals
+// can't show dense meshes, large ones can show more than the usual cap.
+fn edge_budget(representation: model::Representation, cells: usize) -> usize {
+    let scale = (cells as f32 / EDGE_BUDGET_CELLS).clamp(EDGE_BUDGET_SCALE_RANGE.0, EDGE_BUDGET_SCALE_RANGE.1);
+    (representation.max_edges() as f32 * scale) as usize
+}
+
+// Bound network requests and PyMOL runs by `--timeout <SECS>`.
+fn set_timeout_arg(value: Option<&String>) {
+    match value.and_then(|v| v.parse::<u64>().ok()) {
+        Some(secs) if secs > 0 => model::set_timeout(secs),
+        _ => error_close("--timeout requires a positive number of seconds."),
+    }
 }
 
 fn parse_args() -> Option<Command> {
@@ -354,32 +1085,177 @@ fn parse_args() -> Option<Command> {
     }
 
     if args[1] == "search" {
+        let mut terms = Vec::new();
+        let mut limit = DEFAULT_SEARCH_LIMIT;
+        let mut start = 0;
+        let mut json = false;
+
+        let mut i = 2;
+        while i < args.len() {
+            match args[i].as_str() {
+                "--limit" | "-l" => {
+                    match args.get(i + 1).and_then(|v| v.parse::<usize>().ok()) {
+                        Some(n) if n > 0 => limit = n.min(MAX_SEARCH_LIMIT),
+                        _ => error_close("--limit requires a positive number of results."),
+                    }
+                    i += 2;
+                }
+                "--start" => {
+                    match args.get(i + 1).and_then(|v| v.parse::<usize>().ok()) {
+                        Some(n) => start = n,
+                        None => error_close("--start requires a result offset (e.g., 10)."),
+                    }
+                    i += 2;
+                }
+                "--timeout" => {
+                    set_timeout_arg(args.get(i + 1));
+                    i += 2;
+                }
+                "--quiet" | "--verbose" => {
+                    model::set_verbosity(if args[i] == "--quiet" { model::Verbosity::Quiet } else { model::Verbosity::Verbose });
+                    i += 1;
+                }
+                "--json" => {
+                    json = true;
+                    i += 1;
+                }
+                _ => {
+                    terms.push(args[i].clone());
+                    i += 1;
+                }
+            }
+        }
+
+        if terms.is_empty() {
+            error_close("Usage: pepterm search <query> [--limit <n>] [--start <offset>] [--json]");
+        }
+        return Some(Command::Search(SearchArgs { query: terms.join(" "), limit, start, json }));
+    }
+
+    if args[1] == "info" {
+        if args.len() < 3 {
+            error_close("Usage: pepterm info <PDB_ID> [--timeout <secs>]");
+        }
+        if args.get(3).is_some_and(|a| a == "--timeout") {
+            set_timeout_arg(args.get(4));
+        }
+        return Some(Command::Info(args[2].clone()));
+    }
+
+    if args[1] == "colors" {
+        return Some(Command::Colors);
+    }
+
+    if args[1] == "chains" {
         if args.len() < 3 {
-            error_close("Usage: pepterm search <query>");
+            error_close("Usage: pepterm chains <PDB_ID|file> [--timeout <secs>]");
         }
-        let query = args[2..].join(" ");
-        return Some(Command::Search(query));
+        if args.get(3).is_some_and(|a| a == "--timeout") {
+            set_timeout_arg(args.get(4));
+        }
+        return Some(Command::Chains(args[2].clone()));
     }
 
     if args[1] == "cache" {
         if args.len() >= 3 && args[2] == "clear" {
-            return Some(Command::CacheClear);
+            return Some(Command::CacheClear(args.get(3).cloned()));
+        } else if args.len() >= 3 && args[2] == "list" {
+            return Some(Command::CacheList);
         } else {
             return Some(Command::CacheInfo);
         }
     }
 
+    let config = load_config();
+
+    // `pepterm bench <input> [options]` loads like the viewer, then times
+    // frames off screen instead of opening the UI.
+    let mut bench_frames = (args[1] == "bench").then_some(DEFAULT_BENCH_FRAMES);
+    // `pepterm prefetch <inputs> [options]` loads the inputs into the cache
+    // and exits; `@FILE` reads further inputs from a file.
+    let prefetch = args[1] == "prefetch";
+
     let mut inputs = Vec::new();
-    let mut color_scheme = ColorScheme::Coolwarm;
+    let mut alphafold = false;
+    let mut pick_chain = false;
+    let mut list_chains = false;
+    let mut thickness = 1;
+    let mut stereo = None;
+    let mut split = Split::Horizontal;
+    let mut contacts = false;
+    let mut transparent_bg = false;
+    let mut max_edges = None;
+    let mut rotate = config.rotate.unwrap_or(true);
+    let mut transitions = config.transitions.unwrap_or(true);
+    let mut color_explicit = config.color.is_some();
+    let mut color_scheme = config.color.unwrap_or(ColorScheme::Coolwarm);
     let mut chain: Option<String> = None;
+    let mut reverse = false;
+    let mut ensemble = false;
+    let mut overlay = false;
+    let mut align = false;
+    let mut perf = false;
+    let mut invert = false;
+    let mut demo = false;
+    let mut alpha = 1.0;
+    let mut ensemble_rate = DEFAULT_ENSEMBLE_RATE;
+    let mut smooth = config.smooth.unwrap_or(false);
+    let mut ribbon = config.ribbon.unwrap_or(false);
+    let mut no_cache = false;
+    let mut disulfides = false;
+    let mut hbonds = false;
+    let mut cache_ttl: Option<Duration> = None;
+    let mut quality = config.quality.unwrap_or(model::Quality::Medium);
+    let mut representation = model::Representation::Cartoon;
+    let mut render_path: Option<String> = None;
+    let mut render_size: Option<(u16, u16)> = None;
+    let mut region: Option<screen::Region> = None;
+    let mut svg_path: Option<String> = None;
+    let mut dump_obj_path: Option<String> = None;
+    let mut center: Option<(String, String)> = None;
+    let mut select: Option<String> = None;
+    let mut assembly = model::Assembly::Asym;
+    let mut fov = VIEWPORT_FOV;
+    let mut orientation = INITIAL_ORIENTATION;
+    let mut highlights: Vec<model::Highlight> = Vec::new();
+    let mut annotations: Vec<model::Annotation> = Vec::new();
+    let mut pixel = config.pixel.unwrap_or(PixelMode::Braille);
+    let mut color_depth = config.colors;
+    let mut cvd = config.cvd;
+    let mut fps = config.fps.unwrap_or(DEFAULT_FPS);
+    let mut background = config.background;
+
+    // Scroll preferences default from the environment so they can be set once.
+    let mut zoom_speed = env::var("PEPTERM_ZOOM_SPEED").ok()
+        .and_then(|v| v.parse::<f32>().ok())
+        .filter(|&v| v.is_finite() && v > 0.)
+        .or(config.zoom_speed)
+        .unwrap_or(1.);
+    let mut invert_scroll = match env::var("PEPTERM_INVERT_SCROLL") {
+        Ok(v) => v == "1" || v == "true",
+        Err(_) => config.invert_scroll.unwrap_or(false),
+    };
 
-    let mut i = 1;
+    let mut spin_axis = SpinAxis::Yaw;
+    let mut spin_speed = 1.;
+
+    let mut i = if bench_frames.is_some() || prefetch { 2 } else { 1 };
     while i < args.len() {
         match args[i].as_str() {
+            "--frames" if bench_frames.is_some() => {
+                match args.get(i + 1).and_then(|v| v.parse::<usize>().ok()) {
+                    Some(n) if n > 0 => bench_frames = Some(n),
+                    _ => error_close("--frames requires a positive number of frames."),
+                }
+                i += 2;
+            }
             "--color" | "-c" => {
                 if i + 1 < args.len() {
                     match ColorScheme::from_str(&args[i + 1]) {
-                        Some(scheme) => color_scheme = scheme,
+                        Some(scheme) => {
+                            color_scheme = scheme;
+                            color_explicit = true;
+                        }
                         None => {
                             error_close(&format!("Unknown color scheme: {}. Use --help for available options.", args[i + 1]));
                         }
@@ -389,178 +1265,1802 @@ fn parse_args() -> Option<Command> {
                     error_close("--color requires a scheme name. Use --help for available options.");
                 }
             }
-            "--chain" | "-n" => {
+            "--mono" => {
+                match args.get(i + 1).map(|v| ColorScheme::parse_mono(v)) {
+                    Some(Ok(scheme)) => {
+                        color_scheme = scheme;
+                        color_explicit = true;
+                    }
+                    Some(Err(e)) => error_close(&e),
+                    None => error_close("--mono requires a hex color (e.g., #FF8800)."),
+                }
+                i += 2;
+            }
+            "--palette" | "-p" => {
                 if i + 1 < args.len() {
-                    chain = Some(args[i + 1].clone());
+                    match ColorScheme::parse_palette(&args[i + 1]) {
+                        Ok(scheme) => {
+                            color_scheme = scheme;
+                            color_explicit = true;
+                        }
+                        Err(e) => error_close(&e),
+                    }
                     i += 2;
                 } else {
-                    error_close("--chain requires a chain ID (e.g., A, B).");
+                    error_close("--palette requires color stops (e.g., 0,0,255:255,0,0).");
                 }
             }
-            arg if arg.starts_with('-') => {
-                error_close(&format!("Unknown option: {}. Use --help for usage.", arg));
+            "--reverse" | "-r" => {
+                reverse = true;
+                i += 1;
             }
-            _ => {
-                inputs.push(args[i].clone());
+            "--no-cache" => {
+                no_cache = true;
                 i += 1;
             }
-        }
-    }
-
-    if inputs.is_empty() {
-        return None;
-    }
-
-    Some(Command::View(ViewArgs { inputs, chain, color_scheme }))
-}
-
-fn run_search(query: &str) {
-    eprintln!("Searching RCSB PDB for '{}'...", query);
-
-    match model::search_pdb(query) {
-        Ok(results) => {
-            if results.is_empty() {
-                println!("No results found for '{}'", query);
-            } else {
-                println!("\n\x1b[1mSearch Results:\x1b[0m\n");
-                for result in &results {
-                    let title = if result.title.len() > 60 {
-                        format!("{}...", &result.title[..57])
-                    } else {
-                        result.title.clone()
-                    };
-                    println!("  \x1b[1;36m{}\x1b[0m  {}", result.pdb_id, title);
+            "--cache-ttl" => {
+                match args.get(i + 1).and_then(|v| v.parse::<f64>().ok()).filter(|days| days.is_finite() && *days >= 0.) {
+                    Some(days) => cache_ttl = Some(Duration::from_secs_f64(days * 86400.)),
+                    None => error_close("--cache-ttl requires a number of days (e.g., 30)."),
                 }
-                println!("\nUse: pepterm <PDB_ID> to view a structure");
+                i += 2;
             }
-        }
-        Err(e) => {
-            error_close(&format!("Search failed: {}", e));
-        }
-    }
-}
-
-fn main() {
-    let default_panic = std::panic::take_hook();
-    std::panic::set_hook(Box::new(move |info| {
-        cleanup_terminal();
-        default_panic(info);
-    }));
-
-    let command = match parse_args() {
-        Some(cmd) => cmd,
-        None => {
-            print!("{}", HELP_MSG);
-            exit(0);
-        }
-    };
-
-    let args = match command {
-        Command::Search(query) => {
-            run_search(&query);
-            exit(0);
-        }
-        Command::CacheInfo => {
-            match model::cache_info() {
-                Ok((count, size, path)) => {
-                    let size_mb = size as f64 / 1024.0 / 1024.0;
-                    println!("Cache directory: {}", path.display());
-                    println!("Files: {}", count);
-                    println!("Total size: {:.2} MB", size_mb);
-                    println!("\nUse 'pepterm cache clear' to remove cached files.");
+            "--quality" | "-q" => {
+                match args.get(i + 1).and_then(|v| model::Quality::from_str(v)) {
+                    Some(q) => quality = q,
+                    None => error_close("--quality requires one of: low, medium, high."),
                 }
-                Err(e) => error_close(&format!("Failed to get cache info: {}", e)),
+                i += 2;
             }
-            exit(0);
-        }
-        Command::CacheClear => {
-            match model::cache_clear() {
-                Ok(count) => {
-                    println!("Cleared {} cached files.", count);
+            "--repr" => {
+                match args.get(i + 1).and_then(|v| model::Representation::from_str(v)) {
+                    Some(r) => representation = r,
+                    None => error_close("--repr requires one of: cartoon, surface, sticks, ribbon, backbone, trace, tube."),
                 }
-                Err(e) => error_close(&format!("Failed to clear cache: {}", e)),
+                i += 2;
             }
-            exit(0);
-        }
-        Command::View(args) => args,
-    };
-
-    let mut color_scheme = args.color_scheme;
-    let num_models = args.inputs.len();
-
-    let mut models: Vec<model::Model> = Vec::new();
-    let mut model_diagonals: Vec<f32> = Vec::new();
-    let mut model_centers: Vec<three::Point> = Vec::new();
-
-    for input in args.inputs.iter() {
-        let chain_info = match &args.chain {
-            Some(c) => format!(" (chain {})", c),
-            None => String::new(),
-        };
-        eprintln!("Loading {}{}...", input, chain_info);
-
-        match model::new_cartoon(input, args.chain.as_deref(), three::Point::new(0., 0., 0.)) {
-            Ok(mut m) => {
-                m.apply_color_scheme(|t| color_scheme.get_color(t));
-
-                let bounds = m.world_bounds();
-                let center = three::Point::new(
-                    (bounds.0.x + bounds.1.x) / 2.,
-                    (bounds.0.y + bounds.1.y) / 2.,
-                    (bounds.0.z + bounds.1.z) / 2.,
-                );
-                let diagonal = (
-                    (bounds.0.x - bounds.1.x).powi(2) +
-                    (bounds.0.y - bounds.1.y).powi(2) +
-                    (bounds.0.z - bounds.1.z).powi(2)
-                ).sqrt();
-
-                model_centers.push(center);
-                model_diagonals.push(diagonal);
-                models.push(m);
+            "--max-edges" => {
+                match args.get(i + 1).and_then(|v| v.parse::<usize>().ok()) {
+                    Some(n) if n > 0 => max_edges = Some(n),
+                    _ => error_close("--max-edges requires a positive number of edges."),
+                }
+                i += 2;
             }
-            Err(error) => {
-                error_close(&format!("Error loading {}: {}", input, error));
+            "--backbone" => {
+                representation = model::Representation::Backbone;
+                i += 1;
+            }
+            "--disulfides" => {
+                disulfides = true;
+                i += 1;
+            }
+            "--hbonds" => {
+                hbonds = true;
+                i += 1;
+            }
+            "--render" => {
+                match args.get(i + 1) {
+                    Some(path) => render_path = Some(path.clone()),
+                    None => error_close("--render requires an output file (e.g., out.ansi, out.txt)."),
+                }
+                i += 2;
+            }
+            "--svg" => {
+                match args.get(i + 1) {
+                    Some(path) => svg_path = Some(path.clone()),
+                    None => error_close("--svg requires an output file (e.g., out.svg)."),
+                }
+                i += 2;
+            }
+            "--dump-obj" => {
+                match args.get(i + 1) {
+                    Some(path) => dump_obj_path = Some(path.clone()),
+                    None => error_close("--dump-obj requires an output file (e.g., out.obj)."),
+                }
+                i += 2;
+            }
+            "--size" => {
+                match args.get(i + 1).and_then(|v| parse_size(v)) {
+                    Some(size) => render_size = Some(size),
+                    None => error_close("--size requires WIDTHxHEIGHT in characters (e.g., 100x40)."),
+                }
+                i += 2;
+            }
+            "--region" => {
+                let values: Option<Vec<u16>> = args.get(i + 1)
+                    .map(|v| v.split(',').map(|n| n.trim().parse::<u16>().ok()).collect::<Option<Vec<u16>>>())
+                    .unwrap_or(None);
+                match values.as_deref() {
+                    Some(&[x, y, w, h]) if w > 0 && h > 1 => region = Some((x, y, w, h)),
+                    _ => error_close("--region requires X,Y,WIDTH,HEIGHT in characters, at least 2 rows high (e.g., 0,0,80,20)."),
+                }
+                i += 2;
+            }
+            "--smooth" | "-s" => {
+                smooth = true;
+                i += 1;
+            }
+            "--ribbon" => {
+                ribbon = true;
+                i += 1;
+            }
+            "--ensemble" | "-e" => {
+                ensemble = true;
+                i += 1;
+            }
+            "--overlay" => {
+                overlay = true;
+                i += 1;
+            }
+            "--align" => {
+                align = true;
+                i += 1;
+            }
+            "--invert" => {
+                invert = true;
+                i += 1;
+            }
+            "--perf" => {
+                perf = true;
+                i += 1;
+            }
+            "--ensemble-rate" => {
+                match args.get(i + 1).and_then(|v| v.parse::<f32>().ok()) {
+                    Some(rate) if rate > 0. => ensemble_rate = rate,
+                    _ => error_close("--ensemble-rate requires a positive number of states per second."),
+                }
+                i += 2;
+            }
+            "--zoom-speed" => {
+                match args.get(i + 1).and_then(|v| v.parse::<f32>().ok()) {
+                    Some(speed) if speed.is_finite() && speed > 0. => zoom_speed = speed,
+                    _ => error_close("--zoom-speed requires a positive multiplier (e.g., 0.5, 2)."),
+                }
+                i += 2;
+            }
+            "--spin-axis" => {
+                match args.get(i + 1).and_then(|v| SpinAxis::from_str(v)) {
+                    Some(axis) => spin_axis = axis,
+                    None => error_close("--spin-axis requires one of: yaw, pitch, both."),
+                }
+                i += 2;
+            }
+            "--spin-speed" => {
+                match args.get(i + 1).and_then(|v| v.parse::<f32>().ok()) {
+                    Some(speed) if speed > 0. => spin_speed = speed,
+                    _ => error_close("--spin-speed requires a positive multiplier (e.g., 0.5, 3)."),
+                }
+                i += 2;
+            }
+            "--colors" => {
+                match args.get(i + 1).and_then(|v| screen::ColorDepth::from_str(v)) {
+                    Some(depth) => color_depth = Some(depth),
+                    None => error_close("--colors requires one of: 256, truecolor."),
+                }
+                i += 2;
+            }
+            "--cvd" => {
+                match args.get(i + 1).and_then(|v| screen::Cvd::from_str(v)) {
+                    Some(deficiency) => cvd = Some(deficiency),
+                    None => error_close("--cvd requires one of: protanopia, deuteranopia, tritanopia."),
+                }
+                i += 2;
+            }
+            "--pixel" => {
+                match args.get(i + 1).and_then(|v| PixelMode::from_str(v)) {
+                    Some(p) => pixel = p,
+                    None => error_close("--pixel requires one of: braille, block."),
+                }
+                i += 2;
+            }
+            "--fps" => {
+                match args.get(i + 1).and_then(|v| v.parse::<f32>().ok()) {
+                    Some(f) if f > 0. => fps = f,
+                    _ => error_close("--fps requires a positive frame rate."),
+                }
+                i += 2;
+            }
+            "--bg" => {
+                match args.get(i + 1).and_then(|v| parse_hex_color(v)) {
+                    Some(color) => background = Some(color),
+                    None => error_close("--bg requires a hex color (e.g., #101018)."),
+                }
+                i += 2;
+            }
+            "--highlight" => {
+                match args.get(i + 1).and_then(|v| model::Highlight::from_str(v)) {
+                    Some(highlight) => highlights.push(highlight),
+                    None => error_close("--highlight requires CHAIN/START-END (e.g., A/10-20)."),
+                }
+                i += 2;
+            }
+            "--label" => {
+                match args.get(i + 1).and_then(|v| model::Annotation::from_str(v)) {
+                    Some(annotation) => annotations.push(annotation),
+                    None => error_close("--label requires CHAIN/RESI:TEXT (e.g., A/57:His57)."),
+                }
+                i += 2;
+            }
+            "--fov" => {
+                let range = (FOV_RANGE.0.to_degrees(), FOV_RANGE.1.to_degrees());
+                match args.get(i + 1).and_then(|v| v.parse::<f32>().ok()) {
+                    Some(deg) if (range.0..=range.1).contains(&deg) => fov = deg.to_radians(),
+                    _ => error_close(&format!("--fov requires an angle in degrees between {:.0} and {:.0}.", range.0, range.1)),
+                }
+                i += 2;
+            }
+            "--rotate-to" | "--orient" => {
+                let angles = args.get(i + 1).and_then(|v| v.split_once(','))
+                    .map(|(yaw, pitch)| (yaw.trim().parse::<f32>(), pitch.trim().parse::<f32>()));
+                match angles {
+                    Some((Ok(yaw), Ok(pitch))) if yaw.is_finite() && pitch.is_finite() => {
+                        orientation = (yaw.to_radians(), pitch.to_radians());
+                    }
+                    _ => error_close(&format!("{} requires yaw and pitch in degrees (e.g., 0,0 or 90,-30).", args[i])),
+                }
+                i += 2;
+            }
+            "--invert-scroll" => {
+                invert_scroll = true;
+                i += 1;
+            }
+            "--alpha" => {
+                match args.get(i + 1).and_then(|v| v.parse::<f32>().ok()) {
+                    Some(a) if a > 0. && a <= 1. => alpha = a,
+                    _ => error_close("--alpha requires an opacity between 0 and 1 (e.g., 0.5)."),
+                }
+                i += 2;
+            }
+            "--demo" | "--loop-demo" => {
+                demo = true;
+                i += 1;
+            }
+            "--rotate" | "--no-rotate" => {
+                rotate = args[i] == "--rotate";
+                i += 1;
+            }
+            "--no-transitions" => {
+                transitions = false;
+                i += 1;
+            }
+            "--center" => {
+                match args.get(i + 1).and_then(|v| v.split_once('/')) {
+                    Some((c, resi)) if !c.is_empty() && !resi.is_empty() => match model::check_center(c, resi) {
+                        Ok(()) => center = Some((c.to_string(), resi.to_string())),
+                        Err(message) => error_close(&message),
+                    },
+                    _ => error_close("--center requires CHAIN/RESI (e.g., A/57)."),
+                }
+                i += 2;
+            }
+            "--assembly" => {
+                match args.get(i + 1).and_then(|v| model::Assembly::from_str(v)) {
+                    Some(a) => assembly = a,
+                    None => error_close("--assembly requires one of: asym, bio1."),
+                }
+                i += 2;
+            }
+            "--select" => {
+                match args.get(i + 1) {
+                    Some(value) => match model::check_selection(value) {
+                        Ok(()) => select = Some(value.trim().to_string()),
+                        Err(message) => error_close(&message),
+                    },
+                    None => error_close("--select requires a PyMOL selection (e.g., \"resi 1-50 and chain A\")."),
+                }
+                i += 2;
+            }
+            "--timeout" => {
+                set_timeout_arg(args.get(i + 1));
+                i += 2;
+            }
+            "--quiet" | "--verbose" => {
+                model::set_verbosity(if args[i] == "--quiet" { model::Verbosity::Quiet } else { model::Verbosity::Verbose });
+                i += 1;
+            }
+            "--source" => {
+                match args.get(i + 1).map(|s| s.to_lowercase()).as_deref() {
+                    Some("pdb") => alphafold = false,
+                    Some("alphafold") | Some("af") => alphafold = true,
+                    _ => error_close("--source requires one of: pdb, alphafold."),
+                }
+                i += 2;
+            }
+            "--thickness" => {
+                match args.get(i + 1).and_then(|v| v.parse::<u8>().ok()) {
+                    Some(t @ 1..=2) => thickness = t,
+                    _ => error_close("--thickness requires 1 or 2."),
+                }
+                i += 2;
+            }
+            "--split" => {
+                match args.get(i + 1).and_then(|v| Split::from_str(v)) {
+                    Some(direction) => split = direction,
+                    None => error_close("--split requires one of: horizontal, vertical."),
+                }
+                i += 2;
+            }
+            "--stereo" => {
+                match args.get(i + 1).and_then(|v| Stereo::from_str(v)) {
+                    Some(mode) => stereo = Some(mode),
+                    None => error_close("--stereo requires one of: sbs, anaglyph."),
+                }
+                i += 2;
+            }
+            "--pick-chain" => {
+                pick_chain = true;
+                i += 1;
+            }
+            "--list-chains" => {
+                list_chains = true;
+                i += 1;
+            }
+            "--contacts" => {
+                contacts = true;
+                i += 1;
+            }
+            "--transparent-bg" => {
+                transparent_bg = true;
+                i += 1;
+            }
+            "--chain" | "-n" => {
+                match args.get(i + 1) {
+                    Some(c) if !c.is_empty() && !c.chars().any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\')) => {
+                        chain = Some(c.clone());
+                    }
+                    _ => error_close("--chain requires a chain ID (e.g., A, B, or a lowercase or multi-letter ID like AA)."),
+                }
+                i += 2;
+            }
+            arg if arg.starts_with('-') => {
+                error_close(&format!("Unknown option: {}. Use --help for usage.", arg));
+            }
+            list if prefetch && list.starts_with('@') => {
+                inputs.extend(read_input_list(&list[1..]));
+                i += 1;
+            }
+            _ => {
+                inputs.push(args[i].clone());
+                i += 1;
+            }
+        }
+    }
+
+    if inputs.is_empty() {
+        if prefetch {
+            error_close("Usage: pepterm prefetch <ID> <ID> ... | @<list.txt> [options]");
+        }
+        return None;
+    }
+
+    // `--source alphafold` reads bare identifiers as UniProt accessions.
+    if alphafold {
+        for input in &mut inputs {
+            let is_file = input.contains('.') || input.contains('/') || input.contains('\\');
+            if !is_file && model::alphafold_accession(input).is_none() {
+                *input = format!("af:{}", input);
+            }
+        }
+    }
+
+    if list_chains {
+        if inputs.len() > 1 {
+            error_close("--list-chains works with a single structure only.");
+        }
+        return Some(Command::Chains(inputs.remove(0)));
+    }
+
+    if pick_chain && inputs.len() > 1 {
+        error_close("--pick-chain works with a single structure only.");
+    }
+
+    if stereo.is_some() && inputs.len() > 1 {
+        error_close("--stereo works with a single structure only.");
+    }
+
+    if contacts && (inputs.len() > 1 || stereo.is_some() || svg_path.is_some()) {
+        error_close("--contacts works with a single structure only, without --stereo or --svg.");
+    }
+
+    if stereo.is_some() && svg_path.is_some() {
+        error_close("--stereo cannot be combined with --svg.");
+    }
+
+    // Each is framed for its own canvas, so one run writes only one of them.
+    if render_path.is_some() && svg_path.is_some() {
+        error_close("--svg cannot be combined with --render; write each in its own run.");
+    }
+
+    if ensemble && inputs.len() > 1 {
+        error_close("--ensemble works with a single structure only.");
+    }
+
+    if overlay && inputs.len() < 2 {
+        error_close("--overlay needs at least two structures.");
+    }
+
+    if align && inputs.len() < 2 {
+        error_close("--align needs at least two structures.");
+    }
+    // Aligned structures share a frame, which only shows when overlaid.
+    if align {
+        overlay = true;
+    }
+
+    // Side-by-side viewports below a readable size would be slivers; the SVG
+    // canvas is large enough for any practical count.
+    if !overlay && svg_path.is_none() && !prefetch {
+        let (columns, rows) = render_size.or_else(|| view_size(region).map(|(w, h)| (w, h.saturating_sub(1)))).unwrap_or(DEFAULT_RENDER_SIZE);
+        let (cells, min_cells, unit) = match split {
+            Split::Horizontal => (columns, MIN_VIEWPORT_CELLS.0, "columns"),
+            Split::Vertical => (rows, MIN_VIEWPORT_CELLS.1, "rows"),
+        };
+        let max = (cells / min_cells).max(1) as usize;
+        if inputs.len() > max {
+            error_close(&format!(
+                "Too many structures for the current terminal; max {} in {} {} ({} each). Try --overlay or a larger terminal.",
+                max, cells, unit, min_cells
+            ));
+        }
+    }
+
+    // Small molecules have no chain to ramp along; default to element colors.
+    if !color_explicit && inputs.iter().all(|input| model::is_small_molecule(input) || model::is_xyz(input)) {
+        color_scheme = ColorScheme::Element;
+    }
+    // AlphaFold models carry pLDDT in the B-factor column; show confidence.
+    if !color_explicit && inputs.iter().all(|input| model::alphafold_accession(input).is_some()) {
+        color_scheme = ColorScheme::Plddt;
+    }
+
+    // Trace and tube are drawn from CA atoms alone, with no bonds to add.
+    if matches!(representation, model::Representation::Trace | model::Representation::Tube) {
+        for (flag, name) in [(&mut disulfides, "--disulfides"), (&mut hbonds, "--hbonds")] {
+            if *flag {
+                eprintln!("Warning: {} is not drawn with --repr {}.", name, representation.name());
+                *flag = false;
+            }
+        }
+    }
+
+    if ensemble && center.is_some() {
+        error_close("--center cannot be combined with --ensemble.");
+    }
+    if align && center.is_some() {
+        error_close("--center cannot be combined with --align.");
+    }
+    if demo {
+        rotate = true;
+    }
+    // A painted background would cover exactly what should show through.
+    if transparent_bg {
+        background = None;
+    }
+
+    let max_edges = max_edges.unwrap_or_else(|| {
+        let (columns, rows) = render_size.or_else(|| view_size(region)).unwrap_or(DEFAULT_RENDER_SIZE);
+        edge_budget(representation, columns as usize * rows as usize)
+    });
+
+    let load_options = model::LoadOptions { chain, no_cache, quality, representation, center, select, max_edges, assembly, cache_ttl, disulfides, hbonds, preview: None };
+    Some(Command::View(Box::new(ViewArgs { inputs, load_options, color_scheme, reverse, ensemble, ensemble_rate, smooth, ribbon, render_path, bench_frames, prefetch, render_size, region, svg_path, dump_obj_path, zoom_speed, invert_scroll, spin_axis, spin_speed, transitions, fov, orientation, highlights, annotations, pixel, fps, background,
+        cache_limit_mb: config.cache_limit_mb,
+        pick_chain,
+        thickness,
+        stereo,
+        split,
+        contacts,
+        rotate,
+        transparent_bg,
+        overlay,
+        align,
+        perf,
+        color_depth,
+        cvd,
+        invert,
+        demo,
+        alpha,
+    })))
+}
+
+fn run_search(args: &SearchArgs) {
+    let query = &args.query;
+    progress!("Searching RCSB PDB for '{}'...", query);
+
+    match model::search_pdb(query, args.start, args.limit) {
+        Ok((results, _)) if args.json => {
+            let entries: Vec<String> = results.iter()
+                .map(|r| format!("  {{\"pdb_id\": {}, \"title\": {}}}", json_quote(&r.pdb_id), json_quote(&r.title)))
+                .collect();
+            if entries.is_empty() {
+                println!("[]");
+            } else {
+                println!("[\n{}\n]", entries.join(",\n"));
+            }
+        }
+        Ok((results, total)) => {
+            if results.is_empty() && args.start > 0 && total > 0 {
+                println!("--start {} is beyond the {} results for '{}'", args.start, total, query);
+            } else if results.is_empty() {
+                println!("No results found for '{}'", query);
+            } else {
+                println!(
+                    "\n\x1b[1mSearch Results:\x1b[0m showing {}-{} of {} matches\n",
+                    args.start + 1, args.start + results.len(), total
+                );
+                for result in &results {
+                    let title = if result.title.len() > 60 {
+                        format!("{}...", &result.title[..57])
+                    } else {
+                        result.title.clone()
+                    };
+                    println!("  \x1b[1;36m{}\x1b[0m  {}", result.pdb_id, title);
+                }
+                if args.start + results.len() < total {
+                    println!(
+                        "\nMore results: pepterm search {} --start {} --limit {}",
+                        query, args.start + results.len(), args.limit
+                    );
+                }
+                println!("\nUse: pepterm <PDB_ID> to view a structure");
+            }
+        }
+        Err(e) => {
+            error_close(&format!("Search failed: {}", e));
+        }
+    }
+}
+
+// A string as a JSON literal, quoted and escaped.
+fn json_quote(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// Print each color scheme beside a swatch: a gradient drawn with half blocks
+// (two samples per cell) for residue ramps, the legend's swatches (minus
+// its title) otherwise.
+fn run_colors() {
+    let depth = screen::ColorDepth::detect();
+    let fg = |c: screen::Rgb| screen::color_escape(c, depth, false);
+    let bg = |c: screen::Rgb| screen::color_escape(c, depth, true);
+    let example_chains: Vec<String> = ["A", "B", "C", "D"].iter().map(|c| c.to_string()).collect();
+
+    for scheme in ColorScheme::builtin() {
+        let mut line = format!("  {:<10}", scheme.name());
+        if scheme.color_by() == model::ColorBy::Position {
+            for i in 0..SWATCH_WIDTH {
+                let t = |half: usize| (2 * i + half) as f32 / (2 * SWATCH_WIDTH - 1) as f32;
+                line.push_str(&format!("{}{}▌", fg(scheme.get_color(t(0))), bg(scheme.get_color(t(1)))));
+            }
+            line.push_str("\x1b[0m");
+        } else {
+            for (text, color) in legend_spans(&scheme, false, &example_chains).into_iter().skip(2) {
+                match color {
+                    Some(color) => line.push_str(&format!("{}{}\x1b[0m", fg(color), text)),
+                    None => line.push_str(&text),
+                }
+            }
+        }
+        println!("{}", line);
+    }
+}
+
+fn run_info(pdb_id: &str) {
+    progress!("Fetching metadata for {}...", pdb_id);
+
+    match model::fetch_pdb_metadata(pdb_id) {
+        Ok(info) => {
+            let or_unknown = |v: Option<String>| v.unwrap_or_else(|| "unknown".to_string());
+            let list_or_none = |v: &[String]| if v.is_empty() { "none".to_string() } else { v.join(", ") };
+
+            println!("\n\x1b[1;36m{}\x1b[0m  {}\n", info.pdb_id, info.title);
+            println!("  \x1b[1mMethod\x1b[0m:      {}", or_unknown(info.method));
+            println!("  \x1b[1mResolution\x1b[0m:  {}", or_unknown(info.resolution.map(|r| format!("{:.2} Å", r))));
+            println!("  \x1b[1mChains\x1b[0m:      {}", list_or_none(&info.chains));
+            println!("  \x1b[1mLigands\x1b[0m:     {}", list_or_none(&info.ligands));
+            println!("\nUse: pepterm {} to view this structure", info.pdb_id);
+        }
+        Err(e) => {
+            error_close(&format!("Info lookup failed: {}", e));
+        }
+    }
+}
+
+// Loaded structures and how each is framed.
+struct Scene {
+    models: Vec<model::Model>,
+    centers: Vec<three::Point>,
+    diagonals: Vec<f32>,
+    // Side-by-side viewports; ensemble states share a single one.
+    viewports: usize,
+    initial_distance: f32,
+    // Input name drawn under each side-by-side viewport, in a character row
+    // of `label_height` pixels kept clear of the drawing.
+    labels: Vec<String>,
+    label_height: u16,
+    // Whether several viewports sit side by side or stacked.
+    split: Split,
+    // Draw a single structure as a stereo pair.
+    stereo: Option<Stereo>,
+    // Keep the right of the screen for a contact map.
+    contacts: bool,
+    // Draw every model into the single viewport, superimposed.
+    overlay: bool,
+    // Residue labels, drawn in the single viewport.
+    annotations: Vec<model::Annotation>,
+    // Yaw and pitch each side-by-side viewport is turned by on top of the
+    // shared orbit, and the viewport that drags currently turn, if any.
+    turns: Vec<(f32, f32)>,
+    active: Option<usize>,
+}
+
+impl Scene {
+    // Whether one structure (or one overlay) fills the whole screen from the
+    // orbit camera, so screen points map back onto it.
+    fn full_view(&self) -> bool {
+        self.viewports == 1 && self.stereo.is_none() && !self.contacts
+    }
+
+    // Whether each structure has a viewport of its own.
+    fn side_by_side(&self) -> bool {
+        self.viewports > 1 && self.stereo.is_none() && !self.contacts
+    }
+
+    // Side-by-side viewport containing a screen pixel.
+    fn viewport_at(&self, point: &screen::Point, width: u16, height: u16) -> Option<usize> {
+        if !self.side_by_side() {
+            return None;
+        }
+        (0..self.viewports).find(|&i| {
+            let (x, y, viewport_width, viewport_height) = self.split.viewport(i as u16, self.viewports as u16, width, height);
+            (x as i32..(x + viewport_width) as i32).contains(&point.x) && (y as i32..(y + viewport_height) as i32).contains(&point.y)
+        })
+    }
+
+    // Models drawn: the current state, or every model when overlaid or side by side.
+    fn shown(&self, current_state: usize) -> &[model::Model] {
+        if self.overlay || self.viewports > 1 {
+            &self.models
+        } else {
+            slice::from_ref(&self.models[current_state])
+        }
+    }
+}
+
+// Smallest box around the world bounds of all the given models.
+fn union_bounds(models: &[model::Model]) -> (three::Point, three::Point) {
+    let mut bounds = models[0].world_bounds();
+    for m in models {
+        let (min, max) = m.world_bounds();
+        bounds.0 = three::Point::new(bounds.0.x.min(min.x), bounds.0.y.min(min.y), bounds.0.z.min(min.z));
+        bounds.1 = three::Point::new(bounds.1.x.max(max.x), bounds.1.y.max(max.y), bounds.1.z.max(max.z));
+    }
+    bounds
+}
+
+// Size in characters of the terminal area the viewer draws in, status line
+// included: the --region rectangle, or the whole terminal.
+fn view_size(region: Option<screen::Region>) -> Option<(u16, u16)> {
+    match region {
+        Some((_, _, width, height)) => Some((width, height)),
+        None => terminal::size().ok(),
+    }
+}
+
+// Pixel size of the area a single view is drawn in: the SVG canvas, the
+// --render size, or the terminal less the status line; minus the second eye
+// of side-by-side stereo or the contact map.
+fn drawing_size(args: &ViewArgs) -> (f32, f32) {
+    let (width, height) = if args.svg_path.is_some() {
+        SVG_CANVAS_SIZE
+    } else {
+        let (columns, rows) = args.render_size.filter(|_| args.render_path.is_some()).unwrap_or_else(|| {
+            view_size(args.region).map(|(w, h)| (w, h.saturating_sub(1))).unwrap_or(DEFAULT_RENDER_SIZE)
+        });
+        let (cell_width, cell_height) = args.pixel.cell_size();
+        ((columns.max(1) * cell_width) as u32, (rows.max(1) * cell_height) as u32)
+    };
+    let (width, height) = (width as f32, height as f32);
+
+    if args.stereo == Some(Stereo::SideBySide) {
+        (width / 2., height)
+    } else if args.contacts {
+        (width - height.min(width / 2.), height)
+    } else {
+        (width, height)
+    }
+}
+
+// Distance at which the bounding box of every model, seen from the orbit,
+// fits the drawing area: wide or flat structures are framed by whichever
+// screen dimension they fill first, rather than by their diagonal alone.
+fn fit_distance(models: &[model::Model], orbit: &Orbit, (width, height): (f32, f32)) -> f32 {
+    let camera = three::Camera::new(
+        three::Point::new(0., 0., 0.),
+        -orbit.yaw, -orbit.pitch, orbit.roll,
+        VIEWPORT_DISTANCE, orbit.fov,
+        screen::Screen::with_size(1, 1),
+    );
+    let (min, max) = union_bounds(models);
+    let corners: Vec<three::Point> = (0..8)
+        .map(|i| three::Point::new(
+            if i & 1 == 0 { min.x } else { max.x },
+            if i & 2 == 0 { min.y } else { max.y },
+            if i & 4 == 0 { min.z } else { max.z },
+        ))
+        .collect();
+    camera.fit_distance(&corners, &orbit.center, width, height, FIT_FILL)
+}
+
+// Camera orbit around the scene.
+#[derive(Clone, Copy)]
+struct Orbit {
+    yaw: f32,
+    pitch: f32,
+    roll: f32,
+    distance: f32,
+    center: three::Point,
+    fov: f32,
+    // Near and far clip planes as depth offsets from the center, in model
+    // diagonals; ±CLIP_LIMIT leaves that side unclipped.
+    clip: (f32, f32),
+}
+
+impl Orbit {
+    // Camera position orbiting a center at the given distance.
+    fn camera_position(&self, center: &three::Point, distance: f32) -> three::Point {
+        three::Point::new(
+            self.yaw.sin() * self.pitch.cos() * distance + center.x,
+            self.pitch.sin() * distance + center.y,
+            -self.yaw.cos() * self.pitch.cos() * distance + center.z,
+        )
+    }
+
+    // Camera-space depth slab for a model of the given diagonal viewed from
+    // `distance`, or None when neither plane has been moved in.
+    fn slab(&self, distance: f32, diagonal: f32) -> Option<(f32, f32)> {
+        let (near, far) = self.clip;
+        if near <= -CLIP_LIMIT && far >= CLIP_LIMIT {
+            return None;
+        }
+        let near = if near <= -CLIP_LIMIT { f32::NEG_INFINITY } else { distance + near * diagonal };
+        let far = if far >= CLIP_LIMIT { f32::INFINITY } else { distance + far * diagonal };
+        Some((near, far))
+    }
+}
+
+// An eased glide of the camera's yaw, pitch, distance and pan toward a
+// target orbit, advanced once per frame.
+struct CameraTween {
+    start: Orbit,
+    target: Orbit,
+    started: time::Instant,
+}
+
+impl CameraTween {
+    // Yaw is taken the short way round, so a long auto-rotation doesn't
+    // unwind turn by turn.
+    fn new(from: &Orbit, target: Orbit) -> CameraTween {
+        let turn = (from.yaw - target.yaw + f32::consts::PI).rem_euclid(f32::consts::TAU) - f32::consts::PI;
+        let start = Orbit { yaw: target.yaw + turn, pitch: from.pitch, distance: from.distance, center: from.center, ..target };
+        CameraTween { start, target, started: time::Instant::now() }
+    }
+
+    // The orbit at this moment, and whether the glide has finished.
+    fn step(&self) -> (Orbit, bool) {
+        let t = (self.started.elapsed().as_secs_f32() / TRANSITION_TIME.as_secs_f32()).min(1.);
+        let eased = t * t * (3. - 2. * t);
+        let mix = |a: f32, b: f32| a + (b - a) * eased;
+        let (a, b) = (&self.start, &self.target);
+        let orbit = Orbit {
+            yaw: mix(a.yaw, b.yaw),
+            pitch: mix(a.pitch, b.pitch),
+            distance: mix(a.distance, b.distance),
+            center: three::Point::new(mix(a.center.x, b.center.x), mix(a.center.y, b.center.y), mix(a.center.z, b.center.z)),
+            ..self.target
+        };
+        (orbit, t >= 1.)
+    }
+}
+
+// Color every model with a scheme, as after pressing `c`.
+fn recolor(models: &mut [model::Model], scheme: &ColorScheme, reverse: bool, highlights: &[model::Highlight]) {
+    for m in models {
+        m.apply_color_scheme(|t| scheme.get_color(if reverse { 1.0 - t } else { t }), scheme.color_by(), highlights);
+    }
+}
+
+// Draw the bounding box of the models in gray, plus short X/Y/Z axes in red,
+// green and blue pointing out from `center`.
+fn plot_overlay(camera: &mut three::Camera, models: &[model::Model], center: &three::Point, diagonal: f32) {
+    let (min, max) = union_bounds(models);
+    let corner = |i: usize| three::Point::new(
+        if i & 1 == 0 { min.x } else { max.x },
+        if i & 2 == 0 { min.y } else { max.y },
+        if i & 4 == 0 { min.z } else { max.z },
+    );
+    let gray = screen::Rgb::new(120, 120, 120);
+    for i in 0..8 {
+        for bit in [1, 2, 4] {
+            if i & bit == 0 {
+                camera.edge_color(&corner(i), &corner(i | bit), gray, gray);
+            }
+        }
+    }
+
+    let length = diagonal * AXIS_LENGTH;
+    for (offset, color) in [
+        ((length, 0., 0.), screen::Rgb::new(255, 60, 60)),
+        ((0., length, 0.), screen::Rgb::new(60, 255, 60)),
+        ((0., 0., length), screen::Rgb::new(60, 120, 255)),
+    ] {
+        let tip = three::Point::new(center.x + offset.0, center.y + offset.1, center.z + offset.2);
+        camera.edge_color(center, &tip, color, color);
+    }
+}
+
+// Plot the scene from the orbit: one viewport for a single structure (showing
+// `current_state` of an ensemble), side-by-side viewports otherwise.
+fn plot_scene(camera: &mut three::Camera, scene: &Scene, orbit: &Orbit, current_state: usize) {
+    camera.roll = orbit.roll;
+    camera.viewport_fov = orbit.fov;
+    camera.slab = orbit.slab(orbit.distance, scene.diagonals[current_state]);
+    camera.screen.annotations.clear();
+
+    if let Some(stereo) = scene.stereo {
+        camera.screen.labels.clear();
+        let model = &scene.models[current_state];
+        let position = orbit.camera_position(&orbit.center, orbit.distance);
+        let half_separation = orbit.distance * STEREO_SEPARATION / 2.;
+        camera.convergence = orbit.distance;
+
+        match stereo {
+            // Left eye on the left, for parallel free-viewing.
+            Stereo::SideBySide => {
+                let half_width = camera.screen.width / 2;
+                let height = camera.screen.height;
+                for (i, side) in [-1., 1.].into_iter().enumerate() {
+                    camera.eye_offset = side * half_separation;
+                    camera.plot_model_in_viewport(model, position, -orbit.yaw, -orbit.pitch, i as u16 * half_width, 0, half_width, height);
+                }
+            }
+            // Left eye through the red filter, right eye through cyan.
+            Stereo::Anaglyph => {
+                camera.coordinates = position;
+                camera.yaw = -orbit.yaw;
+                camera.pitch = -orbit.pitch;
+                for (side, tint) in [(-1., screen::Rgb::new(255, 0, 0)), (1., screen::Rgb::new(0, 255, 255))] {
+                    camera.eye_offset = side * half_separation;
+                    camera.screen.tint = Some(tint);
+                    camera.plot_model_colored_edges(model);
+                    camera.screen.reset_depth();
+                }
+                camera.screen.tint = None;
+            }
+        }
+        camera.eye_offset = 0.;
+    } else if scene.contacts {
+        camera.screen.labels.clear();
+        let width = camera.screen.width - contact_map_size(&camera.screen);
+        let height = camera.screen.height;
+        let position = orbit.camera_position(&orbit.center, orbit.distance);
+        camera.plot_model_in_viewport(&scene.models[current_state], position, -orbit.yaw, -orbit.pitch, 0, 0, width, height);
+    } else if scene.viewports == 1 {
+        camera.screen.labels.clear();
+        camera.coordinates = orbit.camera_position(&orbit.center, orbit.distance);
+        camera.yaw = -orbit.yaw;
+        camera.pitch = -orbit.pitch;
+        for model in scene.shown(current_state) {
+            camera.plot_model_colored_edges(model);
+        }
+        camera.screen.annotations = scene.annotations.iter()
+            .filter_map(|annotation| {
+                let point = scene.shown(current_state).iter().find_map(|m| {
+                    Some(m.model_to_world(&m.residue_position(&annotation.chain, annotation.resi)?))
+                })?;
+                Some((camera.project(&point)?, annotation.text.clone()))
+            })
+            .collect();
+    } else {
+        let (width, height) = (camera.screen.width, camera.screen.height);
+        let viewport = |i: usize| scene.split.viewport(i as u16, scene.viewports as u16, width, height);
+        let (_, _, viewport_width, viewport_height) = viewport(0);
+        // The label takes the last character row wholly inside each viewport;
+        // the model is drawn above it.
+        let drawing_height = |i: usize| {
+            let (_, y, _, height) = viewport(i);
+            let label_top = ((y + height) / scene.label_height * scene.label_height).saturating_sub(scene.label_height);
+            label_top.saturating_sub(y).max(1)
+        };
+        // Columns are tall and rows wide, so each split is limited by a different side.
+        let limiting_size = match scene.split {
+            Split::Horizontal => (viewport_width as f32).min(drawing_height(0) as f32 / 2.0),
+            Split::Vertical => (viewport_width as f32).min(drawing_height(0) as f32 * 2.0),
+        };
+        let scale_factor = limiting_size * 0.012;
+        camera.screen.labels = scene.labels.iter().enumerate()
+            .map(|(i, label)| {
+                let (x, y, width, height) = viewport(i);
+                (x, width, y + height, label.clone())
+            })
+            .collect();
+
+        for (i, model) in scene.models.iter().enumerate() {
+            let (x, y, _, _) = viewport(i);
+            let base_distance = scene.diagonals[i] * INITIAL_DISTANCE_MULTIPLIER * scale_factor;
+            let model_distance = base_distance * (orbit.distance / scene.initial_distance);
+            camera.slab = orbit.slab(model_distance, scene.diagonals[i]);
+            let (turn_yaw, turn_pitch) = scene.turns.get(i).copied().unwrap_or((0., 0.));
+            let turned = Orbit { yaw: orbit.yaw + turn_yaw, pitch: orbit.pitch + turn_pitch, ..*orbit };
+
+            camera.plot_model_in_viewport(
+                model,
+                turned.camera_position(&scene.centers[i], model_distance),
+                -turned.yaw,
+                -turned.pitch,
+                x,
+                y,
+                viewport_width,
+                drawing_height(i),
+            );
+        }
+
+        // Frame the active viewport, in empty cells only so it never hides the model.
+        if let Some(active) = scene.active {
+            let (x, y, _, _) = viewport(active);
+            let (left, top) = (x as i32, y as i32);
+            let (right, bottom) = (left + viewport_width as i32 - 1, top + viewport_height as i32 - 1);
+            let corners = [(left, top), (right, top), (right, bottom), (left, bottom)];
+            for (i, &(start_x, start_y)) in corners.iter().enumerate() {
+                let (end_x, end_y) = corners[(i + 1) % corners.len()];
+                camera.screen.line_color(
+                    &screen::Point::new(start_x, start_y),
+                    &screen::Point::new(end_x, end_y),
+                    ACTIVE_VIEWPORT_COLOR,
+                    ACTIVE_VIEWPORT_COLOR,
+                    (f32::INFINITY, f32::INFINITY),
+                );
+            }
+        }
+    }
+}
+
+// Side of the square contact map in pixels, leaving at least half the
+// screen width to the model.
+fn contact_map_size(screen: &screen::Screen) -> u16 {
+    screen.height.min(screen.width / 2)
+}
+
+// Draw the contact map of a state at the right edge of the screen, reusing
+// `map` unless the screen size or state changed.
+fn plot_contacts(camera: &mut three::Camera, model: &model::Model, map: &mut Option<(usize, contacts::ContactMap)>, state: usize, threshold: f32, color_fn: impl Fn(f32) -> screen::Rgb) {
+    let size = contact_map_size(&camera.screen);
+    if !map.as_ref().is_some_and(|(s, m)| *s == state && m.size == size) {
+        *map = Some((state, contacts::ContactMap::new(&model.residues, size)));
+    }
+    let x_offset = camera.screen.width - size;
+    if let Some((_, map)) = map {
+        map.plot(&mut camera.screen, x_offset, threshold, color_fn);
+    }
+}
+
+// Render a single frame at the initial orientation and write it to a file:
+// plain text for `.txt`, ANSI truecolor otherwise.
+fn render_to_file(scene: &Scene, orbit: &Orbit, path: &str, args: &ViewArgs) {
+    let (columns, rows) = args.render_size.unwrap_or_else(|| {
+        view_size(args.region).map(|(w, h)| (w, h.saturating_sub(1))).unwrap_or(DEFAULT_RENDER_SIZE)
+    });
+
+    let mut camera = offscreen_camera(columns, rows, args);
+    plot_scene(&mut camera, scene, orbit, 0);
+    if scene.contacts {
+        plot_contacts(&mut camera, &scene.models[0], &mut None, 0, CONTACT_THRESHOLD, |t| args.color_scheme.get_color(t));
+    }
+
+    let ansi = !path.to_lowercase().ends_with(".txt");
+    match fs::write(path, args.pixel.export(&camera.screen, ansi)) {
+        Ok(()) => progress!("Rendered {}x{} to {}", columns, rows, path),
+        Err(e) => error_close(&format!("Failed to write {}: {}", path, e)),
+    }
+}
+
+// Camera drawing onto a screen of `columns` x `rows` characters that is
+// never shown on the terminal.
+fn offscreen_camera(columns: u16, rows: u16, args: &ViewArgs) -> three::Camera {
+    let mut camera = three::Camera::new(
+        three::Point::new(0., 0., 0.),
+        0., 0., 0.,
+        VIEWPORT_DISTANCE, VIEWPORT_FOV,
+        screen::Screen::with_size(
+            columns.max(1) * args.pixel.cell_size().0,
+            rows.max(1) * args.pixel.cell_size().1,
+        ),
+    );
+    camera.antialias = args.smooth;
+    camera.ribbon = args.ribbon;
+    camera.screen.background = args.background;
+    camera.screen.thickness = args.thickness;
+    camera.screen.color_depth = args.color_depth.unwrap_or(screen::ColorDepth::TrueColor);
+    camera.screen.cvd = args.cvd;
+    camera.screen.invert = args.invert;
+    camera.screen.alpha = args.alpha;
+    camera
+}
+
+// Time plotting and encoding frames of the scene turning from the initial
+// orientation, as the viewer would with auto-rotation, and print the median
+// and 95th percentile frame times. The size is fixed rather than taken from
+// the terminal so runs compare across machines and sessions.
+fn bench(scene: &Scene, orbit: &Orbit, frames: usize, args: &ViewArgs) {
+    let (columns, rows) = args.render_size.unwrap_or(DEFAULT_RENDER_SIZE);
+    let mut camera = offscreen_camera(columns, rows, args);
+    let status = [("bench".to_string(), None)];
+    let edges: usize = scene.shown(0).iter().map(|m| m.colored_edges.len() + m.edges.len()).sum();
+
+    let mut orbit = *orbit;
+    let mut times = Vec::with_capacity(frames);
+    let mut bytes = 0;
+    for _ in 0..frames {
+        let start = time::Instant::now();
+        camera.screen.clear();
+        plot_scene(&mut camera, scene, &orbit, 0);
+        bytes += args.pixel.diff_frame(&mut camera.screen, &status).len();
+        times.push(start.elapsed());
+        args.spin_axis.spin(&mut orbit, args.spin_speed);
+    }
+
+    times.sort();
+    let median = times[times.len() / 2];
+    let p95 = times[(times.len() * 95 / 100).min(times.len() - 1)];
+    println!("{} frames of {} edges at {}x{}", frames, edges, columns, rows);
+    println!("median {:.2} ms, p95 {:.2} ms", median.as_secs_f64() * 1000., p95.as_secs_f64() * 1000.);
+    println!("{:.0} edges/s, {:.0} bytes/frame", edges as f64 / median.as_secs_f64(), bytes as f64 / frames as f64);
+}
+
+// Project the scene at the initial orientation onto a pixel canvas and write
+// it as SVG. Multiple inputs are laid out as on screen unless overlaid.
+fn render_to_svg(scene: &Scene, orbit: &Orbit, path: &str, background: screen::Rgb) {
+    let (width, height) = SVG_CANVAS_SIZE;
+
+    let mut camera = three::Camera::new(
+        three::Point::new(0., 0., 0.),
+        -orbit.yaw, -orbit.pitch, orbit.roll,
+        VIEWPORT_DISTANCE, orbit.fov,
+        screen::Screen::with_size(1, 1),
+    );
+
+    let mut edges = Vec::new();
+    for i in 0..scene.viewports {
+        let (center, distance) = if scene.viewports == 1 {
+            (orbit.center, orbit.distance)
+        } else {
+            (scene.centers[i], scene.diagonals[i] * INITIAL_DISTANCE_MULTIPLIER)
+        };
+        camera.coordinates = orbit.camera_position(&center, distance);
+
+        let (x, y, viewport_width, viewport_height) = scene.split.viewport(i as u16, scene.viewports as u16, width as u16, height as u16);
+        let models = if scene.viewports == 1 { scene.shown(0) } else { slice::from_ref(&scene.models[i]) };
+        for mut edge in models.iter().flat_map(|m| camera.project_model(m, viewport_width as f32, viewport_height as f32)) {
+            edge.start = (edge.start.0 + x as f32, edge.start.1 + y as f32);
+            edge.end = (edge.end.0 + x as f32, edge.end.1 + y as f32);
+            edges.push(edge);
+        }
+    }
+
+    let count = edges.len();
+    match fs::write(path, svg::to_svg(edges, width, height, background)) {
+        Ok(()) => progress!("Wrote {} lines ({}x{}) to {}", count, width, height, path),
+        Err(e) => error_close(&format!("Failed to write {}: {}", path, e)),
+    }
+}
+
+// Legend explaining what the active scheme's colors mean: a residue-index
+// gradient, element swatches, or one swatch per chain.
+fn legend_spans(scheme: &ColorScheme, reverse: bool, chains: &[String]) -> Vec<screen::Span> {
+    let swatch = |color: screen::Rgb| ("█".to_string(), Some(color));
+    let mut spans = Vec::new();
+
+    match scheme.color_by() {
+        model::ColorBy::Element => {
+            spans.push(("element".to_string(), None));
+            for element in [
+                model::Element::Carbon, model::Element::Nitrogen, model::Element::Oxygen,
+                model::Element::Sulfur, model::Element::Phosphorus, model::Element::Hydrogen,
+            ] {
+                spans.push((" ".to_string(), None));
+                spans.push(swatch(element.cpk_color()));
+                spans.push((element.symbol().to_string(), None));
+            }
+        }
+        model::ColorBy::Chain => {
+            spans.push(("chain".to_string(), None));
+            if chains.is_empty() {
+                spans.push((" (no chain groups)".to_string(), None));
             }
+            for chain in chains {
+                spans.push((" ".to_string(), None));
+                spans.push(swatch(model::chain_color(chain)));
+                spans.push((chain.clone(), None));
+            }
+        }
+        model::ColorBy::Confidence => {
+            spans.push(("pLDDT".to_string(), None));
+            for (score, label) in [(95.0, ">90"), (80.0, "70-90"), (60.0, "50-70"), (0.0, "<50")] {
+                spans.push((" ".to_string(), None));
+                spans.push(swatch(model::plddt_color(score)));
+                spans.push((label.to_string(), None));
+            }
+        }
+        model::ColorBy::SecondaryStructure => {
+            spans.push(("ss".to_string(), None));
+            for ss in model::SecondaryStructure::ALL {
+                spans.push((" ".to_string(), None));
+                spans.push(swatch(ss.color()));
+                spans.push((ss.name().to_string(), None));
+            }
+        }
+        model::ColorBy::Vertex => {
+            spans.push(("obj vertex colors".to_string(), None));
+        }
+        model::ColorBy::Position => {
+            spans.push(("residue N-term ".to_string(), None));
+            for i in 0..LEGEND_WIDTH {
+                let t = i as f32 / (LEGEND_WIDTH - 1) as f32;
+                spans.push(swatch(scheme.get_color(if reverse { 1.0 - t } else { t })));
+            }
+            spans.push((" C-term".to_string(), None));
+        }
+    }
+
+    spans
+}
+
+// Legend naming each overlaid structure next to its color.
+fn overlay_legend_spans(labels: &[String]) -> Vec<screen::Span> {
+    let mut spans = vec![("overlay".to_string(), None)];
+    for (label, color) in labels.iter().zip(OVERLAY_COLORS.iter().cycle()) {
+        spans.push((" ".to_string(), None));
+        spans.push(("█".to_string(), Some(screen::Rgb::new(color.0, color.1, color.2))));
+        spans.push((label.clone(), None));
+    }
+    spans
+}
+
+// Warn when an input's chain list lacks the `--chain` ID, which would load
+// nothing, listing the chains it has. Chain IDs match case-sensitively, so a
+// different case is pointed out. Inputs whose chains can't be listed pass.
+fn warn_missing_chain(input: &str, chain: &str) {
+    let Ok(chains) = model::list_chains(input) else { return };
+    if chains.is_empty() || chains.iter().any(|c| c == chain) {
+        return;
+    }
+    let hint = match chains.iter().find(|c| c.eq_ignore_ascii_case(chain)) {
+        Some(other) => format!("; chain IDs are case-sensitive, did you mean {}?", other),
+        None => String::new(),
+    };
+    eprintln!("Warning: {} has no chain {} (chains: {}){}", input, chain, chains.join(", "), hint);
+}
+
+// Let the user choose one chain from a numbered menu before loading; `None`
+// (all chains) for single-chain inputs, an empty answer or end of input.
+fn pick_chain(input: &str) -> Option<String> {
+    let chains = match model::list_chains(input) {
+        Ok(chains) => chains,
+        Err(e) => {
+            eprintln!("Warning: could not list the chains of {}: {}", input, e);
+            return None;
         }
+    };
+    if chains.len() < 2 {
+        return None;
     }
 
-    terminal::enable_raw_mode().unwrap();
-    execute!(
-        io::stdout(),
-        terminal::EnterAlternateScreen,
-        cursor::Hide,
-        event::EnableMouseCapture,
-        terminal::Clear(terminal::ClearType::All),
-    ).unwrap();
+    eprintln!("Chains in {}:", input);
+    eprintln!("  0) all");
+    for (i, chain) in chains.iter().enumerate() {
+        eprintln!("  {}) {}", i + 1, chain);
+    }
+    loop {
+        eprint!("Choose a chain [0-{}]: ", chains.len());
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line).unwrap_or(0) == 0 {
+            return None;
+        }
+        let choice = line.trim();
+        if choice.is_empty() || choice == "0" || choice.eq_ignore_ascii_case("all") {
+            return None;
+        }
+        let picked = choice.parse::<usize>().ok()
+            .and_then(|n| chains.get(n.wrapping_sub(1)))
+            .or_else(|| chains.iter().find(|c| c.eq_ignore_ascii_case(choice)));
+        match picked {
+            Some(chain) => return Some(chain.clone()),
+            None => eprintln!("Enter a number from the list or a chain ID."),
+        }
+    }
+}
 
-    let max_diagonal = model_diagonals.iter().cloned().fold(0.0f32, f32::max);
+// Human-readable file size.
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 => format!("{:.1} MB", b as f64 / 1024.0 / 1024.0),
+        b if b >= 1024 => format!("{:.1} KB", b as f64 / 1024.0),
+        b => format!("{} B", b),
+    }
+}
+
+// `YYYY-MM-DD HH:MM` in UTC, from days since the epoch via the proleptic
+// Gregorian calendar (Howard Hinnant's civil_from_days).
+fn format_timestamp(time: time::SystemTime) -> String {
+    let secs = time.duration_since(time::UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, rem) = ((secs / 86400) as i64, secs % 86400);
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02} {:02}:{:02} UTC", year, month, day, rem / 3600, rem % 3600 / 60)
+}
+
+// Parse a `WxH` size in terminal characters.
+fn parse_size(s: &str) -> Option<(u16, u16)> {
+    let (w, h) = s.to_lowercase().split_once('x').map(|(w, h)| (w.parse::<u16>(), h.parse::<u16>()))?;
+    match (w, h) {
+        (Ok(w), Ok(h)) if w > 0 && h > 0 => Some((w, h)),
+        _ => None,
+    }
+}
+
+// Read inputs for `pepterm prefetch @FILE`: whitespace-separated IDs, files
+// or URLs, with `#` starting a comment.
+fn read_input_list(path: &str) -> Vec<String> {
+    match fs::read_to_string(path) {
+        Ok(text) => text.lines()
+            .flat_map(|line| line.split('#').next().unwrap_or("").split_whitespace())
+            .map(String::from)
+            .collect(),
+        Err(e) => error_close(&format!("Failed to read input list {}: {}", path, e)),
+    }
+}
+
+// Load every input as the viewer would, leaving the fetched files and PyMOL
+// exports in the cache, and report each one. Exits with an error status when
+// any input failed.
+fn prefetch(args: &ViewArgs) -> ! {
+    let results = load_models(args);
+    let name_width = args.inputs.iter().map(|input| input.chars().count()).max().unwrap_or(0);
+    let mut failed = 0;
+    for (input, result) in args.inputs.iter().zip(&results) {
+        match result {
+            Ok(_) => println!("{:<width$}  ok", input, width = name_width),
+            Err(e) => {
+                failed += 1;
+                println!("{:<width$}  failed: {}", input, e, width = name_width);
+            }
+        }
+    }
+    println!("Cached {} of {} structures.", results.len() - failed, results.len());
+    exit(if failed > 0 { 1 } else { 0 });
+}
 
+// Camera drawing to the terminal (or the --region) with the view's styling.
+fn view_camera(args: &ViewArgs) -> three::Camera {
+    // Screen::new clears the whole terminal, which a region must leave alone.
+    let mut screen = if args.region.is_some() { screen::Screen::with_size(1, 1) } else { screen::Screen::new() };
+    screen.region = args.region;
     let mut camera = three::Camera::new(
         three::Point::new(0., 0., 0.),
         0., 0., 0.,
         VIEWPORT_DISTANCE, VIEWPORT_FOV,
+        screen,
     );
+    camera.antialias = args.smooth;
+    camera.ribbon = args.ribbon;
+    camera.screen.background = args.background;
+    camera.screen.thickness = args.thickness;
+    camera.screen.color_depth = args.color_depth.unwrap_or_else(screen::ColorDepth::detect);
+    camera.screen.cvd = args.cvd;
+    camera.screen.invert = args.invert;
+    camera.screen.alpha = args.alpha;
+    camera.screen.transparent = args.transparent_bg;
+    camera
+}
+
+// Whether loading can be watched: one structure viewed in the whole terminal.
+fn previews_loading(args: &ViewArgs) -> bool {
+    args.inputs.len() == 1 && !args.ensemble && !args.align && args.region.is_none()
+        && args.render_path.is_none() && args.svg_path.is_none() && args.dump_obj_path.is_none()
+        && args.bench_frames.is_none() && !args.prefetch
+        && io::IsTerminal::is_terminal(&io::stdout())
+}
+
+// Load like `load_models`, drawing the edges the loader has read so far
+// whenever loading takes a while, so a huge structure shows its shape
+// instead of a blank terminal. The preview is plain lines framed around what
+// has arrived; the view proper starts from the loaded models.
+fn load_with_preview(args: &ViewArgs, chunks: sync::mpsc::Receiver<model::EdgeChunk>) -> Vec<Result<LoadedInput, String>> {
+    let loaded = thread::scope(|scope| {
+        let loader = scope.spawn(|| load_models(args));
+        let start = time::Instant::now();
+        let frame_duration = Duration::from_secs_f32(1. / args.fps);
+        let mut preview = model::Model::from_edges(Vec::new());
+        let mut camera: Option<three::Camera> = None;
+
+        while !loader.is_finished() {
+            // Redraw only when edges arrive, leaving the loader the CPU otherwise.
+            let mut arrived = chunks.recv_timeout(frame_duration).ok();
+            let mut changed = camera.is_none();
+            while let Some(chunk) = arrived {
+                preview.edges.extend(chunk);
+                changed = true;
+                arrived = chunks.try_recv().ok();
+            }
+            // Quit keys work once the preview is up (raw mode hides Ctrl+C's signal).
+            while camera.is_some() && event::poll(Duration::from_secs(0)).unwrap() {
+                if let Ok(event::Event::Key(key_event)) = event::read() {
+                    let is_ctrl_c = key_event.modifiers == event::KeyModifiers::CONTROL
+                        && key_event.code == event::KeyCode::Char('c');
+                    if is_ctrl_c || key_event.code == event::KeyCode::Char('q') {
+                        graceful_close()
+                    }
+                }
+            }
+            if !changed || preview.edges.is_empty() || start.elapsed() < PREVIEW_DELAY {
+                continue;
+            }
+
+            let camera = camera.get_or_insert_with(|| {
+                terminal::enable_raw_mode().unwrap();
+                execute!(io::stdout(), cursor::Hide, terminal::EnterAlternateScreen, terminal::Clear(terminal::ClearType::All)).unwrap();
+                ALTERNATE_SCREEN.store(true, sync::atomic::Ordering::SeqCst);
+                view_camera(args)
+            });
+
+            let (min, max) = preview.world_bounds();
+            let center = three::Point::new((min.x + max.x) / 2., (min.y + max.y) / 2., (min.z + max.z) / 2.);
+            let orbit = Orbit {
+                yaw: args.orientation.0,
+                pitch: args.orientation.1,
+                roll: 0.,
+                distance: 0.,
+                center,
+                fov: args.fov,
+                clip: (-CLIP_LIMIT, CLIP_LIMIT),
+            };
+            let distance = fit_distance(slice::from_ref(&preview), &orbit, drawing_size(args));
+            args.pixel.fit_to_terminal(&mut camera.screen);
+            camera.screen.clear();
+            camera.viewport_fov = orbit.fov;
+            camera.coordinates = orbit.camera_position(&center, distance);
+            camera.yaw = -orbit.yaw;
+            camera.pitch = -orbit.pitch;
+            // Every few edges, evenly spread, keep frames within the edge budget.
+            let stride = preview.edges.len().div_ceil(args.load_options.max_edges.max(1));
+            for (start, end) in preview.edges.iter().step_by(stride.max(1)) {
+                camera.edge(start, end);
+            }
+            let status = format!("Loading {}... {} edges read", args.inputs[0], preview.edges.len());
+            args.pixel.render_diff(&mut camera.screen, &[(status, None)]);
+        }
+        loader.join().unwrap()
+    });
+
+    // Messages after loading belong on the normal screen.
+    if ALTERNATE_SCREEN.swap(false, sync::atomic::Ordering::SeqCst) {
+        let _ = terminal::disable_raw_mode();
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+    }
+    loaded
+}
+
+// The states loaded for one input and its `--center` residue position.
+type LoadedInput = (Vec<model::Model>, Option<three::Point>);
+
+// Load every input in parallel (at most one PyMOL process per CPU), returning
+// results in input order. Each input yields its states (one unless `--ensemble`)
+// and the `--center` residue position when one was found.
+fn load_models(args: &ViewArgs) -> Vec<Result<LoadedInput, String>> {
+    let total = args.inputs.len();
+    let workers = thread::available_parallelism().map_or(1, |n| n.get()).min(total);
+    let next_input = sync::atomic::AtomicUsize::new(0);
+    let done = sync::atomic::AtomicUsize::new(0);
+    let results = sync::Mutex::new((0..total).map(|_| None).collect::<Vec<_>>());
+
+    let chain_info = match &args.load_options.chain {
+        Some(c) => format!(" (chain {})", c),
+        None => String::new(),
+    };
+    for input in &args.inputs {
+        progress!("Loading {}{}...", input, chain_info);
+    }
+
+    // Alignment needs every structure in one PyMOL session.
+    if args.align {
+        let origin = three::Point::new(0., 0., 0.);
+        return match model::new_aligned(&args.inputs, &args.load_options, origin) {
+            Ok(models) => models.into_iter().map(|m| Ok((vec![m], None))).collect(),
+            Err(e) => error_close(&format!("Error aligning {}: {}", args.inputs.join(", "), e)),
+        };
+    }
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next_input.fetch_add(1, sync::atomic::Ordering::SeqCst);
+                let Some(input) = args.inputs.get(index) else { break };
+
+                let origin = three::Point::new(0., 0., 0.);
+                let loaded = if args.ensemble {
+                    model::new_ensemble(input, &args.load_options, origin).map(|states| (states, None))
+                } else {
+                    model::new_cartoon(input, &args.load_options, origin).map(|(m, center)| (vec![m], center))
+                };
+
+                let finished = done.fetch_add(1, sync::atomic::Ordering::SeqCst) + 1;
+                progress!("[{}/{}] {} done", finished, total, input);
+                results.lock().unwrap()[index] = Some(loaded.map_err(|e| e.to_string()));
+            });
+        }
+    });
+
+    results.into_inner().unwrap()
+        .into_iter()
+        .map(|r| r.unwrap_or_else(|| Err("not loaded".to_string())))
+        .collect()
+}
+
+fn main() {
+    let default_panic = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        cleanup_terminal();
+        default_panic(info);
+    }));
+    restore_on_signals();
+
+    let command = match parse_args() {
+        Some(cmd) => cmd,
+        None => {
+            print!("{}", HELP_MSG);
+            exit(0);
+        }
+    };
+
+    let mut args = match command {
+        Command::Colors => {
+            run_colors();
+            exit(0);
+        }
+        Command::Chains(input) => {
+            match model::list_chains(&input) {
+                Ok(chains) if chains.is_empty() => eprintln!("{} has no chain IDs.", input),
+                Ok(chains) => {
+                    for chain in chains {
+                        println!("{}", chain);
+                    }
+                }
+                Err(e) => error_close(&format!("Failed to list chains of {}: {}", input, e)),
+            }
+            exit(0);
+        }
+        Command::Search(search_args) => {
+            run_search(&search_args);
+            exit(0);
+        }
+        Command::Info(pdb_id) => {
+            run_info(&pdb_id);
+            exit(0);
+        }
+        Command::CacheInfo => {
+            match model::cache_info() {
+                Ok((count, size, path)) => {
+                    let size_mb = size as f64 / 1024.0 / 1024.0;
+                    println!("Cache directory: {}", path.display());
+                    println!("Files: {}", count);
+                    println!("Total size: {:.2} MB", size_mb);
+                    println!("\nUse 'pepterm cache clear' to remove cached files.");
+                }
+                Err(e) => error_close(&format!("Failed to get cache info: {}", e)),
+            }
+            exit(0);
+        }
+        Command::CacheList => {
+            match model::cache_list() {
+                Ok(files) if files.is_empty() => println!("The cache is empty."),
+                Ok(files) => {
+                    let name_width = files.iter().map(|(name, _, _)| name.chars().count()).max().unwrap_or(0);
+                    for (name, size, modified) in &files {
+                        println!("{:<width$}  {:>9}  {}", name, format_size(*size), format_timestamp(*modified), width = name_width);
+                    }
+                }
+                Err(e) => error_close(&format!("Failed to list cache: {}", e)),
+            }
+            exit(0);
+        }
+        Command::CacheClear(filter) => {
+            match model::cache_clear(filter.as_deref()) {
+                Ok(count) => match filter {
+                    Some(f) => println!("Cleared {} cached files matching {}.", count, f),
+                    None => println!("Cleared {} cached files.", count),
+                },
+                Err(e) => error_close(&format!("Failed to clear cache: {}", e)),
+            }
+            exit(0);
+        }
+        Command::View(args) => *args,
+    };
+
+    if args.prefetch {
+        prefetch(&args);
+    }
+
+    let mut color_scheme = args.color_scheme.clone();
+    // A --palette gradient stays in the `c` cycle once cycled away from.
+    let palette = match &args.color_scheme {
+        ColorScheme::Custom(stops) => Some(stops.clone()),
+        _ => None,
+    };
+    let reverse = args.reverse;
+    // Overlaid structures get one solid color each, unless aligned: then the
+    // color scheme runs along the alignment so equivalent residues match.
+    let overlay_colors = args.overlay && !args.align;
+    let num_models = args.inputs.len();
+
+    let mut models: Vec<model::Model> = Vec::new();
+    let mut model_diagonals: Vec<f32> = Vec::new();
+    let mut model_centers: Vec<three::Point> = Vec::new();
+
+    let center_and_diagonal = |bounds: (three::Point, three::Point)| {
+        let center = three::Point::new(
+            (bounds.0.x + bounds.1.x) / 2.,
+            (bounds.0.y + bounds.1.y) / 2.,
+            (bounds.0.z + bounds.1.z) / 2.,
+        );
+        let diagonal = (
+            (bounds.0.x - bounds.1.x).powi(2) +
+            (bounds.0.y - bounds.1.y).powi(2) +
+            (bounds.0.z - bounds.1.z).powi(2)
+        ).sqrt();
+        (center, diagonal)
+    };
 
-    let initial_yaw: f32 = 0.3;
-    let initial_pitch: f32 = 0.2;
-    let initial_distance = max_diagonal * INITIAL_DISTANCE_MULTIPLIER;
+    if args.pick_chain && args.load_options.chain.is_none() {
+        args.load_options.chain = pick_chain(&args.inputs[0]);
+    }
+
+    let loaded_inputs = if previews_loading(&args) {
+        let (sender, chunks) = sync::mpsc::channel();
+        args.load_options.preview = Some(sender);
+        let loaded = load_with_preview(&args, chunks);
+        args.load_options.preview = None;
+        loaded
+    } else {
+        load_models(&args)
+    };
+
+    // Without helix or sheet assignments the ss scheme would paint everything
+    // as loop; color by residue index instead.
+    if matches!(color_scheme, ColorScheme::Ss) && !overlay_colors {
+        let loaded = loaded_inputs.iter().filter_map(|loaded| loaded.as_ref().ok()).flat_map(|(states, _)| states);
+        let reason = match model::structured_fraction(loaded) {
+            None => Some("No secondary structure available (OBJ file, CA trace or an older cache; try --no-cache)".to_string()),
+            Some(fraction) if fraction < SS_MIN_STRUCTURED => Some(format!("PyMOL assigned almost no helix or sheet ({:.0}%)", fraction * 100.)),
+            Some(_) => None,
+        };
+        if let Some(reason) = reason {
+            eprintln!("{}; coloring by residue index instead.", reason);
+            color_scheme = ColorScheme::Rainbow;
+            args.color_scheme = ColorScheme::Rainbow;
+        }
+    }
+
+    // Keep the cache under the configured size, evicting the oldest files.
+    if let Some(limit_mb) = args.cache_limit_mb {
+        match model::cache_prune(limit_mb * 1024 * 1024) {
+            Ok(0) => {}
+            Ok(removed) => progress!("Removed {} old cache files to stay under {} MB", removed, limit_mb),
+            Err(e) => eprintln!("Warning: failed to prune cache: {}", e),
+        }
+    }
+
+    for (index, (input, loaded)) in args.inputs.iter().zip(loaded_inputs).enumerate() {
+        match loaded {
+            Ok((states, residue_center)) => {
+                // Frame every state with the same box so ensembles don't jitter.
+                let (center, diagonal) = center_and_diagonal(union_bounds(&states));
+                if args.load_options.center.is_some() && residue_center.is_none() {
+                    eprintln!("Warning: --center needs a PDB ID or PDB/CIF file; using the bounding-box center for {}.", input);
+                }
+                let center = residue_center.unwrap_or(center);
+
+                let overlay_color = OVERLAY_COLORS[index % OVERLAY_COLORS.len()];
+                for mut m in states {
+                    if overlay_colors {
+                        let color = screen::Rgb::new(overlay_color.0, overlay_color.1, overlay_color.2);
+                        m.apply_color_scheme(|_| color, model::ColorBy::Position, &args.highlights);
+                    } else {
+                        m.apply_color_scheme(|t| color_scheme.get_color(if reverse { 1.0 - t } else { t }), color_scheme.color_by(), &args.highlights);
+                    }
+                    model_centers.push(center);
+                    model_diagonals.push(diagonal);
+                    models.push(m);
+                }
+            }
+            Err(error) => {
+                // Listing chains may take network requests, so only once a load failed.
+                if let Some(chain) = &args.load_options.chain {
+                    warn_missing_chain(input, chain);
+                }
+                error_close(&format!("Error loading {}: {}", input, error));
+            }
+        }
+    }
+
+    // Overlaid models share one frame around all of them, keeping a --center residue.
+    if args.overlay {
+        let (center, diagonal) = center_and_diagonal(union_bounds(&models));
+        let center = if args.load_options.center.is_some() { model_centers[0] } else { center };
+        model_centers = vec![center; models.len()];
+        model_diagonals = vec![diagonal; models.len()];
+    }
+
+    let max_diagonal = model_diagonals.iter().cloned().fold(0.0f32, f32::max);
+    let mut scene = Scene {
+        models,
+        centers: model_centers,
+        diagonals: model_diagonals,
+        viewports: if args.overlay { 1 } else { num_models },
+        initial_distance: max_diagonal * INITIAL_DISTANCE_MULTIPLIER,
+        labels: args.inputs.iter()
+            .map(|input| path::Path::new(input).file_name().map_or(input.clone(), |name| name.to_string_lossy().to_string()))
+            .collect(),
+        label_height: args.pixel.cell_size().1,
+        split: args.split,
+        stereo: args.stereo,
+        contacts: args.contacts,
+        overlay: args.overlay,
+        annotations: args.annotations.clone(),
+        turns: vec![(0., 0.); num_models],
+        active: None,
+    };
+
+    if !scene.annotations.is_empty() && !scene.full_view() {
+        eprintln!("Warning: --label is only drawn with a single structure or --overlay.");
+    }
+    for annotation in &scene.annotations {
+        if scene.models.iter().all(|m| m.residue_position(&annotation.chain, annotation.resi).is_none()) {
+            eprintln!("Warning: no CA atom for --label {}/{}; labels need a PDB ID or a PDB/CIF file.", annotation.chain, annotation.resi);
+        }
+    }
 
-    let mut view_yaw: f32 = initial_yaw;
-    let mut view_pitch: f32 = initial_pitch;
-    let mut distance_to_model = initial_distance;
-    let mut pan_center = model_centers.get(0).cloned().unwrap_or(three::Point::new(0., 0., 0.));
+    if scene.contacts && scene.models.iter().any(|m| m.residues.is_empty()) {
+        error_close("--contacts needs CA atoms, from a PDB ID or a PDB/CIF file.");
+    }
+
+    let mut initial_orbit = Orbit {
+        yaw: args.orientation.0,
+        pitch: args.orientation.1,
+        roll: 0.,
+        distance: scene.initial_distance,
+        center: scene.centers.first().cloned().unwrap_or(three::Point::new(0., 0., 0.)),
+        fov: args.fov,
+        clip: (-CLIP_LIMIT, CLIP_LIMIT),
+    };
+
+    // A single view starts framed to the drawing area; side-by-side viewports
+    // keep scaling each structure by its own diagonal.
+    if scene.viewports == 1 {
+        scene.initial_distance = fit_distance(&scene.models, &initial_orbit, drawing_size(&args));
+        initial_orbit.distance = scene.initial_distance;
+    }
+
+    if let Some(path) = &args.dump_obj_path {
+        let models = scene.shown(0);
+        match fs::write(path, obj::to_obj(models)) {
+            Ok(()) => progress!("Wrote {} edges to {}", models.iter().map(|m| m.colored_edges.len() + m.edges.len()).sum::<usize>(), path),
+            Err(e) => error_close(&format!("Failed to write {}: {}", path, e)),
+        }
+    }
+
+    if let Some(frames) = args.bench_frames {
+        bench(&scene, &initial_orbit, frames, &args);
+        exit(0);
+    }
+
+    if let Some(path) = &args.render_path {
+        render_to_file(&scene, &initial_orbit, path, &args);
+        exit(0);
+    }
+
+    if let Some(path) = &args.svg_path {
+        render_to_svg(&scene, &initial_orbit, path, args.background.unwrap_or(screen::Rgb::black()));
+        exit(0);
+    }
+
+    terminal::enable_raw_mode().unwrap();
+    execute!(io::stdout(), cursor::Hide, event::EnableMouseCapture).unwrap();
+    if args.region.is_none() {
+        execute!(io::stdout(), terminal::EnterAlternateScreen, terminal::Clear(terminal::ClearType::All)).unwrap();
+        ALTERNATE_SCREEN.store(true, sync::atomic::Ordering::SeqCst);
+    }
+
+    let mut camera = view_camera(&args);
+    if args.background.is_none() {
+        camera.screen.terminal_background = detect_terminal_background();
+    }
+
+    let mut orbit = initial_orbit;
+    let mut tween: Option<CameraTween> = None;
     let mut pan_mode = false;
-    let mut auto_rotate = true;
+    let mut auto_rotate = args.rotate;
+    let mut spin_speed = args.spin_speed;
+
+    // Measurement picks (world coordinates), and whether the current click became a drag.
+    let mut picked: Vec<three::Point> = Vec::new();
+    let mut dragged = false;
+    let mut mouse_held = false;
+
+    // Residue under the mouse pointer, and a position still to look up.
+    let mut hovered: Option<String> = None;
+    let mut hover_target: Option<screen::Point> = None;
+    let mut last_hover = time::Instant::now();
+
+    // Chains present in the scene, for the chain legend.
+    let mut show_legend = false;
+    let mut chains: Vec<String> = scene.models.iter()
+        .flat_map(|m| m.colored_edges.iter().filter_map(|e| e.chain_id.clone()))
+        .collect();
+    chains.sort();
+    chains.dedup();
+
+    // Loader caveat (e.g. a trace drawn because PyMOL is missing) for the status bar.
+    let model_note = scene.models.iter().find_map(|m| m.note.clone());
+
+    // Bounding box and axes drawn over the model.
+    let mut show_overlay = false;
+
+    // Contact map for the shown state, and the distance counted as a contact.
+    let mut contact_map: Option<(usize, contacts::ContactMap)> = None;
+    let mut contact_threshold = CONTACT_THRESHOLD;
+
+    // Ensemble playback state.
+    let num_states = if args.ensemble { scene.models.len() } else { 1 };
+    let state_interval = Duration::from_secs_f32(1. / args.ensemble_rate);
+    let mut current_state = 0;
+    let mut ensemble_paused = false;
+    let mut last_state_change = time::Instant::now();
+    let mut last_color_change = time::Instant::now();
 
     let mut mouse_speed: (f32, f32) = (0., 0.);
     let mut last_mouse_position = screen::Point::new(0, 0);
-    let mut last_frame_time = TARGET_DURATION_PER_FRAME;
+    let frame_duration = Duration::from_secs_f32(1. / args.fps);
+    let (cell_width, cell_height) = (args.pixel.cell_size().0 as i32, args.pixel.cell_size().1 as i32);
+    let mut last_frame_time = frame_duration;
 
-    camera.screen.fit_to_terminal::<screen::BrailePixel>();
+    args.pixel.fit_to_terminal(&mut camera.screen);
     camera.screen.clear();
     thread::sleep(Duration::from_millis(50));
 
+    // Whether input arrived (or nothing was drawn yet) since the last frame.
+    let mut needs_redraw = true;
+
+    // Time spent writing the last frame to the terminal, for --perf.
+    let mut render_time = Duration::ZERO;
+
     loop {
         let frame_start = time::Instant::now();
         let mut start_mouse_position = last_mouse_position;
@@ -568,6 +3068,7 @@ fn main() {
 
         while event::poll(Duration::from_secs(0)).unwrap() {
             if let Ok(event) = event::read() {
+                needs_redraw = true;
                 match event {
                     event::Event::Key(key_event) => {
                         let is_ctrl_c = key_event.modifiers == event::KeyModifiers::CONTROL
@@ -576,40 +3077,175 @@ fn main() {
                         if is_ctrl_c || key_event.code == event::KeyCode::Char('q') {
                             graceful_close()
                         }
-                        if key_event.code == event::KeyCode::Char('c') {
-                            color_scheme = color_scheme.next();
-                            for m in &mut models {
-                                m.apply_color_scheme(|t| color_scheme.get_color(t));
+                        if args.demo {
+                            continue;
+                        }
+                        // Alt+number makes that side-by-side viewport the one drags
+                        // turn, or shares the rotation again if it already was.
+                        if let (event::KeyCode::Char(key @ '1'..='9'), true) = (key_event.code, key_event.modifiers.contains(event::KeyModifiers::ALT)) {
+                            let index = key as usize - '1' as usize;
+                            if scene.side_by_side() && index < scene.viewports {
+                                scene.active = if scene.active == Some(index) { None } else { Some(index) };
+                                mouse_speed = (0., 0.);
+                            }
+                            continue;
+                        }
+                        // Overlaid structures keep their own colors.
+                        if !overlay_colors && matches!(key_event.code, event::KeyCode::Char('c') | event::KeyCode::Char('C')) {
+                            color_scheme = if key_event.code == event::KeyCode::Char('C') {
+                                color_scheme.prev(palette.as_deref())
+                            } else {
+                                color_scheme.next(palette.as_deref())
+                            };
+                            recolor(&mut scene.models, &color_scheme, reverse, &args.highlights);
+                        }
+                        if key_event.code == event::KeyCode::Char('{') {
+                            orbit.fov = (orbit.fov - FOV_STEP).max(FOV_RANGE.0);
+                        }
+                        if key_event.code == event::KeyCode::Char('}') {
+                            orbit.fov = (orbit.fov + FOV_STEP).min(FOV_RANGE.1);
+                        }
+                        if key_event.code == event::KeyCode::Char('<') {
+                            camera.screen.brightness = (camera.screen.brightness - BRIGHTNESS_STEP).max(BRIGHTNESS_RANGE.0);
+                        }
+                        if key_event.code == event::KeyCode::Char('>') {
+                            camera.screen.brightness = (camera.screen.brightness + BRIGHTNESS_STEP).min(BRIGHTNESS_RANGE.1);
+                        }
+                        if key_event.code == event::KeyCode::Char('(') {
+                            contact_threshold = (contact_threshold - CONTACT_THRESHOLD_STEP).max(CONTACT_THRESHOLD_RANGE.0);
+                        }
+                        if key_event.code == event::KeyCode::Char(')') {
+                            contact_threshold = (contact_threshold + CONTACT_THRESHOLD_STEP).min(CONTACT_THRESHOLD_RANGE.1);
+                        }
+                        // Move the near (n/N) and far (f/F) clip planes deeper or shallower.
+                        let clip = &mut orbit.clip;
+                        match key_event.code {
+                            event::KeyCode::Char('n') => clip.0 = (clip.0 + CLIP_STEP).min(clip.1 - CLIP_STEP),
+                            event::KeyCode::Char('N') => clip.0 = (clip.0 - CLIP_STEP).max(-CLIP_LIMIT),
+                            event::KeyCode::Char('f') => clip.1 = (clip.1 - CLIP_STEP).max(clip.0 + CLIP_STEP),
+                            event::KeyCode::Char('F') => clip.1 = (clip.1 + CLIP_STEP).min(CLIP_LIMIT),
+                            _ => {}
+                        }
+                        if key_event.code == event::KeyCode::Char('[') {
+                            orbit.roll -= ROLL_STEP;
+                        }
+                        if key_event.code == event::KeyCode::Char(']') {
+                            orbit.roll += ROLL_STEP;
+                        }
+                        if num_states > 1 {
+                            match key_event.code {
+                                event::KeyCode::Char(' ') => ensemble_paused = !ensemble_paused,
+                                event::KeyCode::Char('.') => {
+                                    ensemble_paused = true;
+                                    current_state = (current_state + 1) % num_states;
+                                }
+                                event::KeyCode::Char(',') => {
+                                    ensemble_paused = true;
+                                    current_state = (current_state + num_states - 1) % num_states;
+                                }
+                                _ => {}
                             }
                         }
+                        if key_event.code == event::KeyCode::Char('l') {
+                            show_legend = !show_legend;
+                        }
+                        if key_event.code == event::KeyCode::Char('b') {
+                            show_overlay = !show_overlay;
+                        }
+                        if key_event.code == event::KeyCode::Char('x') {
+                            picked.clear();
+                        }
                         if key_event.code == event::KeyCode::Char('r') {
                             auto_rotate = !auto_rotate;
                         }
+                        if matches!(key_event.code, event::KeyCode::Char('+') | event::KeyCode::Char('=')) {
+                            spin_speed = (spin_speed * SPIN_SPEED_STEP).min(SPIN_SPEED_RANGE.1);
+                        }
+                        if key_event.code == event::KeyCode::Char('-') {
+                            spin_speed = (spin_speed / SPIN_SPEED_STEP).max(SPIN_SPEED_RANGE.0);
+                        }
+                        // Zoom presets change only the distance, keeping orientation and pan.
+                        if let event::KeyCode::Char(key @ '1'..='3') = key_event.code {
+                            let target = Orbit { distance: initial_orbit.distance * ZOOM_PRESETS[key as usize - '1' as usize], ..orbit };
+                            if args.transitions {
+                                tween = Some(CameraTween::new(&orbit, target));
+                            } else {
+                                orbit = target;
+                            }
+                        }
                         if key_event.code == event::KeyCode::Char('0') {
-                            view_yaw = initial_yaw;
-                            view_pitch = initial_pitch;
-                            distance_to_model = initial_distance;
-                            pan_center = model_centers.get(0).cloned().unwrap_or(three::Point::new(0., 0., 0.));
-                            auto_rotate = true;
+                            if args.transitions {
+                                tween = Some(CameraTween::new(&orbit, initial_orbit));
+                            } else {
+                                orbit = initial_orbit;
+                            }
+                            mouse_speed = (0., 0.);
+                            auto_rotate = args.rotate;
+                            spin_speed = args.spin_speed;
+                            scene.turns.fill((0., 0.));
+                            scene.active = None;
                         }
                     }
 
+                    event::Event::Mouse(_) if args.demo => {}
                     event::Event::Mouse(mouse_event) => {
-                        let (x, y) = (mouse_event.column, mouse_event.row);
+                        // Positions relative to the drawing, whose corner a region moves.
+                        let (left, top) = args.region.map_or((0, 0), |(left, top, ..)| (left, top));
+                        let (x, y) = (mouse_event.column.saturating_sub(left), mouse_event.row.saturating_sub(top));
                         match mouse_event.kind {
                             event::MouseEventKind::Down(_) => {
                                 pan_mode = mouse_event.modifiers == event::KeyModifiers::SHIFT;
                                 last_mouse_position.x = x as i32;
                                 last_mouse_position.y = y as i32;
                                 start_mouse_position = last_mouse_position;
+                                dragged = false;
+                                mouse_held = true;
                                 event_count += 1;
                             }
 
+                            // A click without dragging picks the nearest residue.
+                            event::MouseEventKind::Up(_) if !dragged && scene.full_view() => {
+                                let target = screen::Point::new(
+                                    x as i32 * cell_width + cell_width / 2,
+                                    y as i32 * cell_height + cell_height / 2,
+                                );
+                                if let Some(point) = camera.pick_point(scene.shown(current_state), &target, PICK_RADIUS) {
+                                    if picked.len() == 2 {
+                                        picked.clear();
+                                    }
+                                    picked.push(point);
+                                }
+                                mouse_held = false;
+                            }
+
+                            // Side by side, a click makes its viewport the one drags turn.
+                            event::MouseEventKind::Up(_) if !dragged && scene.side_by_side() => {
+                                let target = screen::Point::new(x as i32 * cell_width, y as i32 * cell_height);
+                                if let Some(index) = scene.viewport_at(&target, camera.screen.width, camera.screen.height) {
+                                    scene.active = if scene.active == Some(index) { None } else { Some(index) };
+                                    mouse_speed = (0., 0.);
+                                }
+                                mouse_held = false;
+                            }
+
+                            event::MouseEventKind::Up(_) => {
+                                mouse_held = false;
+                            }
+
+                            // Looked up after the next frame is plotted, at most every HOVER_INTERVAL.
+                            event::MouseEventKind::Moved if scene.full_view() => {
+                                hover_target = Some(screen::Point::new(
+                                    x as i32 * cell_width + cell_width / 2,
+                                    y as i32 * cell_height + cell_height / 2,
+                                ));
+                            }
+
                             event::MouseEventKind::Drag(_) => {
                                 pan_mode = mouse_event.modifiers == event::KeyModifiers::SHIFT;
                                 if !pan_mode {
                                     auto_rotate = false;
                                 }
+                                dragged = true;
                                 let delta_x = x as f32 - start_mouse_position.x as f32;
                                 let delta_y = start_mouse_position.y as f32 - y as f32;
                                 mouse_speed.0 = delta_x / camera.screen.width as f32 * MOUSE_SPEED_MULTIPLIER;
@@ -619,13 +3255,10 @@ fn main() {
                                 event_count += 1;
                             }
 
-                            event::MouseEventKind::ScrollDown => {
-                                distance_to_model += max_diagonal * SCROLL_MULTIPLER;
-                            }
-
-                            event::MouseEventKind::ScrollUp => {
-                                distance_to_model -= max_diagonal * SCROLL_MULTIPLER;
-                                distance_to_model = distance_to_model.max(0.);
+                            event::MouseEventKind::ScrollDown | event::MouseEventKind::ScrollUp => {
+                                let zoom_out = (mouse_event.kind == event::MouseEventKind::ScrollDown) != args.invert_scroll;
+                                let step = max_diagonal * SCROLL_MULTIPLER * args.zoom_speed;
+                                orbit.distance = (orbit.distance + if zoom_out { step } else { -step }).max(0.);
                             }
                             _ => {}
                         }
@@ -635,95 +3268,199 @@ fn main() {
             }
         }
 
+        // After a flick, keep rotating and let the speed decay. Pans and a
+        // held (but still) mouse stop immediately.
         if event_count == 0 {
-            mouse_speed = (0., 0.);
+            if pan_mode || mouse_held {
+                mouse_speed = (0., 0.);
+            } else {
+                mouse_speed.0 *= MOMENTUM_DAMPING;
+                mouse_speed.1 *= MOMENTUM_DAMPING;
+                if mouse_speed.0.hypot(mouse_speed.1) < MOMENTUM_THRESHOLD {
+                    mouse_speed = (0., 0.);
+                }
+            }
             pan_mode = false;
         }
+        let coasting = mouse_speed != (0., 0.);
+        let event_time = frame_start.elapsed();
+
+        // With nothing moving and no input, the last frame is still current:
+        // wait for an event instead of redrawing it.
+        let animating = auto_rotate || coasting || tween.is_some() || hover_target.is_some() || (num_states > 1 && !ensemble_paused);
+        if !needs_redraw && !animating {
+            event::poll(Duration::from_millis(IDLE_POLL_MS)).unwrap();
+            continue;
+        }
+        needs_redraw = false;
+
+        // Mouse deltas are in the rolled screen frame; undo the roll so drags
+        // follow the screen axes the user sees.
+        let (roll_sin, roll_cos) = orbit.roll.sin_cos();
+        let drag = (
+            mouse_speed.0 * roll_cos + mouse_speed.1 * roll_sin,
+            -mouse_speed.0 * roll_sin + mouse_speed.1 * roll_cos,
+        );
 
         if pan_mode {
-            pan_center.x -= mouse_speed.0 * camera.yaw.cos() * max_diagonal * PAN_MULTIPLIER;
-            pan_center.z += mouse_speed.0 * camera.yaw.sin() * max_diagonal * PAN_MULTIPLIER;
-            pan_center.y -= mouse_speed.1 * camera.pitch.cos() * max_diagonal * PAN_MULTIPLIER;
-            pan_center.x += mouse_speed.1 * camera.yaw.sin() * camera.pitch.sin() * max_diagonal * PAN_MULTIPLIER;
-            pan_center.z += mouse_speed.1 * camera.yaw.cos() * camera.pitch.sin() * max_diagonal * PAN_MULTIPLIER;
-        } else if auto_rotate {
-            view_yaw += AUTO_ROTATE_SPEED;
+            orbit.center.x -= drag.0 * camera.yaw.cos() * max_diagonal * PAN_MULTIPLIER;
+            orbit.center.z += drag.0 * camera.yaw.sin() * max_diagonal * PAN_MULTIPLIER;
+            orbit.center.y -= drag.1 * camera.pitch.cos() * max_diagonal * PAN_MULTIPLIER;
+            orbit.center.x += drag.1 * camera.yaw.sin() * camera.pitch.sin() * max_diagonal * PAN_MULTIPLIER;
+            orbit.center.z += drag.1 * camera.yaw.cos() * camera.pitch.sin() * max_diagonal * PAN_MULTIPLIER;
+        } else if auto_rotate && !coasting {
+            args.spin_axis.spin(&mut orbit, spin_speed);
+        } else if let Some(active) = scene.active {
+            scene.turns[active].0 -= drag.0;
+            scene.turns[active].1 -= drag.1;
         } else {
-            view_yaw -= mouse_speed.0;
-            view_pitch -= mouse_speed.1;
+            orbit.yaw -= drag.0;
+            orbit.pitch -= drag.1;
+        }
+
+        // A drag or pan takes the camera back from a glide in progress.
+        if coasting || pan_mode {
+            tween = None;
+        }
+        if let Some(glide) = &tween {
+            let (glided, done) = glide.step();
+            orbit = glided;
+            if done {
+                tween = None;
+            }
         }
 
-        camera.screen.fit_to_terminal::<screen::BrailePixel>();
+        let plot_start = time::Instant::now();
+        args.pixel.fit_to_terminal(&mut camera.screen);
         camera.screen.clear();
 
-        let calc_camera_pos = |center: &three::Point, dist: f32| -> three::Point {
-            three::Point::new(
-                view_yaw.sin() * view_pitch.cos() * dist + center.x,
-                view_pitch.sin() * dist + center.y,
-                -view_yaw.cos() * view_pitch.cos() * dist + center.z,
-            )
-        };
+        if num_states > 1 && !ensemble_paused && last_state_change.elapsed() >= state_interval {
+            current_state = (current_state + 1) % num_states;
+            last_state_change = time::Instant::now();
+        }
+        if args.demo && !overlay_colors && last_color_change.elapsed() >= DEMO_COLOR_INTERVAL {
+            color_scheme = color_scheme.next(palette.as_deref());
+            recolor(&mut scene.models, &color_scheme, reverse, &args.highlights);
+            last_color_change = time::Instant::now();
+        }
 
-        if num_models == 1 {
-            let cam_pos = calc_camera_pos(&pan_center, distance_to_model);
-            camera.coordinates = cam_pos;
-            camera.yaw = -view_yaw;
-            camera.pitch = -view_pitch;
-            camera.plot_model_colored_edges(&models[0]);
-        } else {
-            let viewport_width = camera.screen.width / num_models as u16;
-            let full_height = camera.screen.height;
-            let limiting_size = (viewport_width as f32).min(full_height as f32 / 2.0);
-            let scale_factor = limiting_size * 0.012;
-
-            for (i, model) in models.iter().enumerate() {
-                let base_distance = model_diagonals[i] * INITIAL_DISTANCE_MULTIPLIER * scale_factor;
-                let model_distance = base_distance * (distance_to_model / initial_distance);
-
-                camera.plot_model_in_viewport(
-                    model,
-                    calc_camera_pos(&model_centers[i], model_distance),
-                    -view_yaw,
-                    -view_pitch,
-                    i as u16 * viewport_width,
-                    viewport_width,
-                    full_height,
-                );
+        plot_scene(&mut camera, &scene, &orbit, current_state);
+        if let Some(target) = hover_target.filter(|_| last_hover.elapsed() >= HOVER_INTERVAL) {
+            hovered = camera.pick_residue(scene.shown(current_state), &target, PICK_RADIUS)
+                .map(|(chain, resi)| chain.map_or(resi.to_string(), |chain| format!("{}/{}", chain, resi)));
+            hover_target = None;
+            last_hover = time::Instant::now();
+        }
+        if scene.contacts {
+            plot_contacts(&mut camera, &scene.models[current_state], &mut contact_map, current_state, contact_threshold, |t| color_scheme.get_color(t));
+        }
+        if scene.full_view() {
+            if show_overlay {
+                plot_overlay(&mut camera, scene.shown(current_state), &orbit.center, max_diagonal);
+            }
+            if let [a, b] = picked.as_slice() {
+                camera.edge(a, b);
             }
         }
+        let plot_time = plot_start.elapsed();
 
-        let rotate_msg = if auto_rotate { "auto" } else { "manual" };
+        let rotate_msg = match auto_rotate {
+            true if spin_speed != 1. => format!("auto ×{:.2}", spin_speed),
+            true => "auto".to_string(),
+            false => "manual".to_string(),
+        };
         let fps = 1. / last_frame_time.as_secs_f32();
-        let input_display = if args.inputs.len() == 1 {
+        let input_display = if num_states > 1 {
+            let paused = if ensemble_paused { " paused" } else { "" };
+            format!("{} state {}/{}{}", args.inputs[0], current_state + 1, num_states, paused)
+        } else if args.inputs.len() == 1 {
             args.inputs[0].clone()
         } else if args.inputs.len() <= 4 {
             args.inputs.join("+")
         } else {
             format!("{} structures", args.inputs.len())
         };
+        let input_display = match &model_note {
+            Some(note) => format!("{} ({})", input_display, note),
+            None => input_display,
+        };
+
+        let scheme_display = if overlay_colors {
+            "overlay".to_string()
+        } else if reverse {
+            format!("{}↓", color_scheme.label())
+        } else {
+            color_scheme.label()
+        };
+        let scheme_display = if (camera.screen.brightness - 1.0).abs() > f32::EPSILON {
+            format!("{} {:.0}%", scheme_display, camera.screen.brightness * 100.)
+        } else {
+            scheme_display
+        };
+
+        let measure_display = match picked.as_slice() {
+            [_] => " | pick 1/2".to_string(),
+            [a, b] => format!(" | {:.2} Å", a.distance(b)),
+            _ if scene.contacts => format!(" | contacts <{:.1} Å", contact_threshold),
+            _ => String::new(),
+        };
+        let measure_display = match &hovered {
+            Some(residue) => format!("{} | {}", measure_display, residue),
+            None => measure_display,
+        };
+        // Clip slab as the span of the model's depth still drawn, front to back.
+        let measure_display = if orbit.clip != initial_orbit.clip {
+            let percent = |offset: f32| (offset / (2. * CLIP_LIMIT) + 0.5) * 100.;
+            format!("{} | clip {:.0}–{:.0}%", measure_display, percent(orbit.clip.0), percent(orbit.clip.1))
+        } else {
+            measure_display
+        };
 
         let status_full = format!(
-            "{} | {} | {} | {:.0}fps | [r]otate [c]olor [0]reset [q]uit",
-            input_display, color_scheme.name(), rotate_msg, fps
+            "{}{} | {} | {} | {:.0}fps | [r]otate [c]olor [0]reset [q]uit",
+            input_display, measure_display, scheme_display, rotate_msg, fps
         );
         let status_medium = format!(
-            "{} | {} | {} | {:.0}fps",
-            input_display, color_scheme.name(), rotate_msg, fps
+            "{}{} | {} | {} | {:.0}fps",
+            input_display, measure_display, scheme_display, rotate_msg, fps
         );
-        let status_short = format!("{} | {}", input_display, color_scheme.name());
-
-        let final_msg = match terminal::size().unwrap().0 as usize {
-            w if w > status_full.len() => status_full,
-            w if w > status_medium.len() => status_medium,
-            w if w > status_short.len() => status_short,
-            _ => String::new(),
+        let status_short = format!("{}{} | {}", input_display, measure_display, scheme_display);
+
+        // With --perf, the timings lead and the key hints make way for them.
+        let candidates = if args.perf {
+            let ms = |d: Duration| d.as_secs_f32() * 1000.;
+            let edges: usize = scene.shown(current_state).iter().map(|m| m.colored_edges.len()).sum();
+            let cells = (camera.screen.width as usize).div_ceil(cell_width as usize)
+                * (camera.screen.height as usize).div_ceil(cell_height as usize);
+            let perf_display = format!(
+                "events {:.1} plot {:.1} render {:.1} ms | {} edges {} cells",
+                ms(event_time), ms(plot_time), ms(render_time), edges, cells
+            );
+            vec![
+                format!("{} | {}", perf_display, status_medium),
+                format!("{} | {}", perf_display, status_short),
+                perf_display,
+            ]
+        } else {
+            vec![status_full, status_medium, status_short]
         };
+        let width = view_size(args.region).map_or(DEFAULT_RENDER_SIZE.0, |(w, _)| w) as usize;
+        let final_msg = candidates.into_iter().find(|s| width > screen::visible_width(s)).unwrap_or_default();
 
-        camera.screen.render_with_status::<screen::BrailePixel>(&final_msg);
+        let status_spans = if show_legend && overlay_colors {
+            overlay_legend_spans(&scene.labels)
+        } else if show_legend {
+            legend_spans(&color_scheme, reverse, &chains)
+        } else {
+            vec![(final_msg, None)]
+        };
+        let render_start = time::Instant::now();
+        args.pixel.render_diff(&mut camera.screen, &status_spans);
+        render_time = render_start.elapsed();
 
         let elapsed = frame_start.elapsed();
-        if elapsed < TARGET_DURATION_PER_FRAME {
-            thread::sleep(TARGET_DURATION_PER_FRAME - elapsed);
+        if elapsed < frame_duration {
+            thread::sleep(frame_duration - elapsed);
         }
         last_frame_time = frame_start.elapsed();
     }
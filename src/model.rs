@@ -14,6 +14,88 @@ impl fmt::Display for ParseError {
 
 impl error::Error for ParseError {}
 
+// An OBJ holding no geometry at all, as PyMOL writes when nothing in the
+// selection has the requested representation; told apart from a file that
+// is not an OBJ so the error can name the selection.
+#[derive(Debug)]
+struct EmptyObj;
+
+impl fmt::Display for EmptyObj {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "No geometry found in OBJ")
+    }
+}
+
+impl error::Error for EmptyObj {}
+
+// Chemical element of a vertex, read from OBJ material names.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Element {
+    Carbon,
+    Nitrogen,
+    Oxygen,
+    Sulfur,
+    Phosphorus,
+    Hydrogen,
+    Other,
+}
+
+impl Element {
+    // Parse a material name such as `O`, `elem_N` or `mat-S` into an element.
+    pub fn from_material(name: &str) -> Option<Element> {
+        let symbol = name.rsplit(['_', '-', '.']).next().unwrap_or(name);
+        match symbol.to_uppercase().as_str() {
+            "C" => Some(Element::Carbon),
+            "N" => Some(Element::Nitrogen),
+            "O" => Some(Element::Oxygen),
+            "S" => Some(Element::Sulfur),
+            "P" => Some(Element::Phosphorus),
+            "H" => Some(Element::Hydrogen),
+            "FE" | "ZN" | "MG" | "CA" | "NA" | "CL" | "MN" | "CU" | "SE" | "BR" | "I" | "F" | "K" => Some(Element::Other),
+            _ => None,
+        }
+    }
+
+    // Element symbol, for legends.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Element::Carbon => "C",
+            Element::Nitrogen => "N",
+            Element::Oxygen => "O",
+            Element::Sulfur => "S",
+            Element::Phosphorus => "P",
+            Element::Hydrogen => "H",
+            Element::Other => "other",
+        }
+    }
+
+    // Covalent radius in Å, for inferring bonds from distances.
+    fn covalent_radius(&self) -> f32 {
+        match self {
+            Element::Carbon => 0.76,
+            Element::Nitrogen => 0.71,
+            Element::Oxygen => 0.66,
+            Element::Sulfur => 1.05,
+            Element::Phosphorus => 1.07,
+            Element::Hydrogen => 0.31,
+            Element::Other => 1.3,
+        }
+    }
+
+    // Standard CPK colors.
+    pub fn cpk_color(&self) -> Rgb {
+        match self {
+            Element::Carbon => Rgb::new(144, 144, 144),
+            Element::Nitrogen => Rgb::new(48, 80, 248),
+            Element::Oxygen => Rgb::new(255, 13, 13),
+            Element::Sulfur => Rgb::new(255, 255, 48),
+            Element::Phosphorus => Rgb::new(255, 128, 0),
+            Element::Hydrogen => Rgb::new(255, 255, 255),
+            Element::Other => Rgb::new(255, 20, 147),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct ColoredEdge {
     pub start: three::Point,
@@ -22,16 +104,203 @@ pub struct ColoredEdge {
     pub end_color: Rgb,
     pub start_t: f32,
     pub end_t: f32,
+    pub start_element: Option<Element>,
+    pub end_element: Option<Element>,
+    pub chain_id: Option<String>,
+    pub start_resi: Option<i32>,
+    pub end_resi: Option<i32>,
+    pub start_bfactor: Option<f32>,
+    pub end_bfactor: Option<f32>,
+    pub start_ss: Option<SecondaryStructure>,
+    pub end_ss: Option<SecondaryStructure>,
+    // Colors given with the vertices in an OBJ file (`v x y z r g b`).
+    pub start_vertex_color: Option<Rgb>,
+    pub end_vertex_color: Option<Rgb>,
+    // Color kept under every scheme, e.g. for disulfide bonds.
+    pub fixed_color: Option<Rgb>,
+    // Drawn as a dashed line, e.g. for hydrogen bonds.
+    pub dashed: bool,
+}
+
+// A residue range to emphasize, e.g. `A/50-120`.
+#[derive(Clone)]
+pub struct Highlight {
+    pub chain: String,
+    pub start: i32,
+    pub end: i32,
+}
+
+impl Highlight {
+    // Parse `CHAIN/START-END`.
+    pub fn from_str(s: &str) -> Option<Highlight> {
+        let (chain, range) = s.split_once('/')?;
+        let (start, end) = range.split_once('-')?;
+        let (start, end) = (start.trim().parse().ok()?, end.trim().parse().ok()?);
+        if chain.is_empty() || start > end {
+            return None;
+        }
+        Some(Highlight { chain: chain.to_string(), start, end })
+    }
+
+    fn contains(&self, chain: &str, resi: i32) -> bool {
+        self.chain.eq_ignore_ascii_case(chain) && (self.start..=self.end).contains(&resi)
+    }
+}
+
+// Text shown beside a residue, e.g. `A/57:His57`.
+#[derive(Clone)]
+pub struct Annotation {
+    pub chain: String,
+    pub resi: i32,
+    pub text: String,
+}
+
+impl Annotation {
+    // Parse `CHAIN/RESI:TEXT`.
+    pub fn from_str(s: &str) -> Option<Annotation> {
+        let (residue, text) = s.split_once(':')?;
+        let (chain, resi) = residue.split_once('/')?;
+        let resi = resi.trim().parse().ok()?;
+        if chain.is_empty() || text.is_empty() {
+            return None;
+        }
+        Some(Annotation { chain: chain.to_string(), resi, text: text.to_string() })
+    }
+}
+
+// Color for everything outside the highlighted ranges.
+const DIMMED_COLOR: Rgb = Rgb { r: 80, g: 80, b: 80 };
+// Bright yellow for disulfide bonds, whatever the color scheme.
+const DISULFIDE_COLOR: Rgb = Rgb { r: 255, g: 230, b: 0 };
+
+// Cyan for hydrogen bonds, whatever the color scheme.
+const HBOND_COLOR: Rgb = Rgb { r: 0, g: 210, b: 255 };
+
+// What edge colors are derived from.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorBy {
+    Position,
+    Element,
+    Chain,
+    Confidence,
+    SecondaryStructure,
+    Vertex,
+}
+
+const CHAIN_HUES: u32 = 12;
+
+// Distinct color for a chain: its ID hashed onto evenly spaced hues. The
+// slot stride keeps consecutive IDs (A, B, ...) far apart on the wheel.
+pub fn chain_color(chain: &str) -> Rgb {
+    let hash = chain.bytes().fold(0u32, |h, b| h.wrapping_mul(31).wrapping_add(b as u32));
+    Rgb::from_hue((hash % CHAIN_HUES * 5 % CHAIN_HUES) as f32 / CHAIN_HUES as f32)
+}
+
+// Secondary structure of a residue as PyMOL assigns it (`ss` property).
+#[derive(Clone, Copy, PartialEq)]
+pub enum SecondaryStructure {
+    Helix,
+    Sheet,
+    Loop,
+}
+
+impl SecondaryStructure {
+    pub const ALL: [SecondaryStructure; 3] = [SecondaryStructure::Helix, SecondaryStructure::Sheet, SecondaryStructure::Loop];
+
+    fn from_pymol(code: &str) -> SecondaryStructure {
+        match code {
+            "H" => SecondaryStructure::Helix,
+            "S" => SecondaryStructure::Sheet,
+            _ => SecondaryStructure::Loop,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SecondaryStructure::Helix => "helix",
+            SecondaryStructure::Sheet => "sheet",
+            SecondaryStructure::Loop => "loop",
+        }
+    }
+
+    pub fn color(&self) -> Rgb {
+        match self {
+            SecondaryStructure::Helix => Rgb::new(235, 60, 90),
+            SecondaryStructure::Sheet => Rgb::new(250, 200, 40),
+            SecondaryStructure::Loop => Rgb::new(90, 200, 110),
+        }
+    }
+}
+
+// Fraction of secondary-structure-tagged edges that lie in a helix or sheet,
+// or None when no edge is tagged (OBJ files, CA traces, older caches).
+pub fn structured_fraction<'a>(models: impl IntoIterator<Item = &'a Model>) -> Option<f32> {
+    let (mut tagged, mut structured) = (0usize, 0usize);
+    for edge in models.into_iter().flat_map(|m| &m.colored_edges) {
+        if let Some(ss) = edge.start_ss {
+            tagged += 1;
+            if ss != SecondaryStructure::Loop {
+                structured += 1;
+            }
+        }
+    }
+    (tagged > 0).then(|| structured as f32 / tagged as f32)
 }
 
+// AlphaFold's pLDDT confidence bands (very high, confident, low, very low),
+// read from the B-factor column.
+pub fn plddt_color(plddt: f32) -> Rgb {
+    if plddt > 90.0 {
+        Rgb::new(0, 83, 214)
+    } else if plddt > 70.0 {
+        Rgb::new(101, 203, 243)
+    } else if plddt > 50.0 {
+        Rgb::new(255, 219, 19)
+    } else {
+        Rgb::new(255, 125, 69)
+    }
+}
+
+// Edges parsed between two chunks sent to a loading preview.
+const PREVIEW_CHUNK_EDGES: usize = 20000;
+
+// Plain edges sent while an OBJ is parsed, for drawing before loading ends.
+pub type EdgeChunk = Vec<(three::Point, three::Point)>;
+
 pub struct Model {
     pub points: Vec<three::Point>,
     pub edges: Vec<(three::Point, three::Point)>,
     pub colored_edges: Vec<ColoredEdge>,
     pub position: three::Point,
+    // Caveat to show in the status bar, e.g. a representation fallback.
+    pub note: Option<String>,
+    // CA position of every residue in chain order; empty when unknown.
+    pub residues: Vec<three::Point>,
+    // Chain and number of each of `residues`.
+    pub residue_ids: Vec<(Option<String>, i32)>,
 }
 
 impl Model {
+    // A model of plain edges only, e.g. for a loading preview.
+    pub fn from_edges(edges: EdgeChunk) -> Model {
+        Model {
+            points: Vec::new(),
+            edges,
+            colored_edges: Vec::new(),
+            position: three::Point::new(0., 0., 0.),
+            note: None,
+            residues: Vec::new(),
+            residue_ids: Vec::new(),
+        }
+    }
+
+    // CA position of a residue, in model coordinates.
+    pub fn residue_position(&self, chain: &str, resi: i32) -> Option<three::Point> {
+        self.residue_ids.iter()
+            .position(|(c, r)| c.as_deref() == Some(chain) && *r == resi)
+            .map(|i| self.residues[i])
+    }
+
     pub fn model_to_world(&self, point: &three::Point) -> three::Point {
         three::Point {
             x: point.x + self.position.x,
@@ -93,28 +362,569 @@ impl Model {
         (min_bounds, max_bounds)
     }
 
-    pub fn apply_color_scheme<F>(&mut self, color_fn: F)
+    // Recolor edges from their N-to-C position. Coloring by element, chain,
+    // confidence, secondary structure or vertex uses CPK, per-chain, pLDDT,
+    // helix/sheet/loop or the OBJ file's own colors for edges tagged with one
+    // instead. With highlights, endpoints outside every range are dimmed to
+    // gray. Edges with a fixed color keep it.
+    pub fn apply_color_scheme<F>(&mut self, color_fn: F, color_by: ColorBy, highlights: &[Highlight])
     where
         F: Fn(f32) -> Rgb,
     {
         for edge in &mut self.colored_edges {
-            edge.start_color = color_fn(edge.start_t);
-            edge.end_color = color_fn(edge.end_t);
+            if let Some(color) = edge.fixed_color {
+                edge.start_color = color;
+                edge.end_color = color;
+                continue;
+            }
+            if !highlights.is_empty() {
+                let highlighted = |resi: Option<i32>| match (&edge.chain_id, resi) {
+                    (Some(chain), Some(resi)) => highlights.iter().any(|h| h.contains(chain, resi)),
+                    _ => false,
+                };
+                let (start_lit, end_lit) = (highlighted(edge.start_resi), highlighted(edge.end_resi));
+                if !start_lit || !end_lit {
+                    edge.start_color = if start_lit { color_fn(edge.start_t) } else { DIMMED_COLOR };
+                    edge.end_color = if end_lit { color_fn(edge.end_t) } else { DIMMED_COLOR };
+                    continue;
+                }
+            }
+
+            if let (ColorBy::Chain, Some(chain)) = (color_by, &edge.chain_id) {
+                edge.start_color = chain_color(chain);
+                edge.end_color = edge.start_color;
+                continue;
+            }
+            if let (ColorBy::Confidence, Some(start), Some(end)) = (color_by, edge.start_bfactor, edge.end_bfactor) {
+                edge.start_color = plddt_color(start);
+                edge.end_color = plddt_color(end);
+                continue;
+            }
+            if let (ColorBy::SecondaryStructure, Some(start), Some(end)) = (color_by, edge.start_ss, edge.end_ss) {
+                edge.start_color = start.color();
+                edge.end_color = end.color();
+                continue;
+            }
+            if let (ColorBy::Vertex, Some(start), Some(end)) = (color_by, edge.start_vertex_color, edge.end_vertex_color) {
+                edge.start_color = start;
+                edge.end_color = end;
+                continue;
+            }
+            edge.start_color = match edge.start_element {
+                Some(element) if color_by == ColorBy::Element => element.cpk_color(),
+                _ => color_fn(edge.start_t),
+            };
+            edge.end_color = match edge.end_element {
+                Some(element) if color_by == ColorBy::Element => element.cpk_color(),
+                _ => color_fn(edge.end_t),
+            };
+        }
+    }
+}
+
+// Cartoon detail level, mapped to PyMOL's `cartoon_sampling`.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Quality {
+    Low,
+    Medium,
+    High,
+}
+
+impl Quality {
+    pub fn from_str(s: &str) -> Option<Quality> {
+        match s.to_lowercase().as_str() {
+            "low" => Some(Quality::Low),
+            "medium" => Some(Quality::Medium),
+            "high" => Some(Quality::High),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Quality::Low => "low",
+            Quality::Medium => "medium",
+            Quality::High => "high",
+        }
+    }
+
+    fn sampling(&self) -> u32 {
+        match self {
+            Quality::Low => 2,
+            Quality::Medium => 6,
+            Quality::High => 14,
+        }
+    }
+}
+
+// Which form of a fetched entry to show: the deposited asymmetric unit, or
+// the first biological assembly PyMOL builds from the mmCIF.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Assembly {
+    Asym,
+    Bio1,
+}
+
+impl Assembly {
+    pub fn from_str(s: &str) -> Option<Assembly> {
+        match s.to_lowercase().as_str() {
+            "asym" => Some(Assembly::Asym),
+            "bio1" => Some(Assembly::Bio1),
+            _ => None,
+        }
+    }
+}
+
+// Atoms beyond which a biological assembly is dropped for the asymmetric
+// unit, since exporting e.g. a whole viral capsid can take many minutes.
+const MAX_ASSEMBLY_ATOMS: usize = 300_000;
+
+// Line PyMOL prints (followed by the atom count) when it made that switch.
+const ASSEMBLY_FALLBACK_MARKER: &str = "pepterm-assembly-fallback";
+
+// File next to a cached assembly OBJ that holds the asymmetric unit instead,
+// recording the assembly's atom count.
+fn assembly_fallback_path(obj_path: &path::Path) -> path::PathBuf {
+    obj_path.with_extension("fallback")
+}
+
+fn warn_assembly_fallback(pdb_id: &str, atoms: usize) {
+    eprintln!(
+        "Biological assembly of {} has {} atoms (over {}); showing the asymmetric unit instead.",
+        pdb_id, atoms, MAX_ASSEMBLY_ATOMS
+    );
+}
+
+// PyMOL representation to export.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Representation {
+    Cartoon,
+    Surface,
+    Sticks,
+    Ribbon,
+    // Thin cylinders straight between CA atoms, for huge assemblies.
+    Backbone,
+    Trace,
+    Tube,
+}
+
+impl Representation {
+    pub fn from_str(s: &str) -> Option<Representation> {
+        match s.to_lowercase().as_str() {
+            "cartoon" => Some(Representation::Cartoon),
+            "surface" => Some(Representation::Surface),
+            "sticks" => Some(Representation::Sticks),
+            "ribbon" => Some(Representation::Ribbon),
+            "backbone" => Some(Representation::Backbone),
+            "trace" => Some(Representation::Trace),
+            "tube" => Some(Representation::Tube),
+            _ => None,
+        }
+    }
+
+    // Name as used by PyMOL's `show` command, except for the backbone (a
+    // ribbon with its own settings). Traces and tubes are built natively from
+    // CA atoms and never reach PyMOL.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Representation::Cartoon => "cartoon",
+            Representation::Surface => "surface",
+            Representation::Sticks => "sticks",
+            Representation::Ribbon => "ribbon",
+            Representation::Backbone => "backbone",
+            Representation::Trace => "trace",
+            Representation::Tube => "tube",
+        }
+    }
+
+    fn pymol_name(&self) -> &'static str {
+        match self {
+            Representation::Backbone => "ribbon",
+            _ => self.name(),
+        }
+    }
+
+    // Edge cap for a full-size terminal. Surfaces are dense triangle meshes
+    // and need a higher cap to stay legible.
+    pub fn max_edges(&self) -> usize {
+        match self {
+            Representation::Surface => 150000,
+            _ => DEFAULT_MAX_EDGES,
+        }
+    }
+}
+
+// Options controlling how a structure is turned into geometry.
+#[derive(Clone)]
+pub struct LoadOptions {
+    pub chain: Option<String>,
+    pub no_cache: bool,
+    pub quality: Quality,
+    pub representation: Representation,
+    pub center: Option<(String, String)>,
+    pub select: Option<String>,
+    // Edges kept after decimation.
+    pub max_edges: usize,
+    // Asymmetric unit or biological assembly, for fetched PDB IDs.
+    pub assembly: Assembly,
+    // Age after which files for fetched entries are fetched again; kept
+    // forever when unset.
+    pub cache_ttl: Option<time::Duration>,
+    // Also export SG-SG bonds, drawn in DISULFIDE_COLOR.
+    pub disulfides: bool,
+    // Also export polar contacts, drawn dashed in HBOND_COLOR.
+    pub hbonds: bool,
+    // Receives edges in chunks while an OBJ is parsed.
+    pub preview: Option<sync::mpsc::Sender<EdgeChunk>>,
+}
+
+impl LoadOptions {
+    // Whether a cached file for a fetched entry is older than --cache-ttl.
+    fn expired(&self, path: &path::Path) -> bool {
+        self.cache_ttl.is_some_and(|ttl| {
+            fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(|modified| modified.elapsed().is_ok_and(|age| age > ttl))
+        })
+    }
+
+    // Whether a file for a fetched entry can be reused from the cache.
+    fn cache_hit(&self, path: &path::Path) -> bool {
+        path.exists() && !self.no_cache && !self.expired(path)
+    }
+
+    // PyMOL settings applied before the representation is exported.
+    fn pymol_settings(&self) -> String {
+        let mut settings = format!("set cartoon_sampling, {}", self.quality.sampling());
+        if self.representation == Representation::Backbone {
+            // One straight segment per CA pair instead of a smoothed spline.
+            settings.push_str("\nset ribbon_sampling, 1\nset ribbon_as_cylinders, 1\nset ribbon_radius, 0.2");
+        }
+        settings
+    }
+
+    // Cache file stem for a structure, so differently-loaded variants coexist.
+    fn cache_stem(&self, base: &str) -> String {
+        let mut suffix = format!("{}_{}", self.representation.name(), self.quality.name());
+        if self.assembly == Assembly::Bio1 {
+            suffix = format!("bio1_{}", suffix);
+        }
+        if self.disulfides {
+            suffix = format!("ss_{}", suffix);
+        }
+        if self.hbonds {
+            suffix = format!("hb_{}", suffix);
+        }
+        // Sticks and surfaces cached before they were split by element
+        // would stay gray under element coloring.
+        if matches!(self.representation, Representation::Sticks | Representation::Surface) {
+            suffix = format!("el_{}", suffix);
+        }
+        if let Some(select) = &self.select {
+            suffix = format!("sel{:08x}_{}", fnv1a(select.as_bytes()), suffix);
+        }
+        match &self.chain {
+            Some(c) => format!("{}_{}_{}", base, chain_file_tag(c), suffix),
+            None => format!("{}_{}", base, suffix),
+        }
+    }
+
+    // File next to a cached OBJ holding the `--center` residue's CA position.
+    fn center_path(&self, obj_path: &path::Path) -> Option<path::PathBuf> {
+        let (chain, resi) = self.center.as_ref()?;
+        let stem = obj_path.file_stem()?.to_string_lossy();
+        Some(obj_path.with_file_name(format!("{}_center_{}_{}.txt", stem, chain_file_tag(chain), resi)))
+    }
+
+    // Load an OBJ exported with these options, explaining an empty one in
+    // terms of the chain or selection that produced it.
+    fn load_export(&self, path: &path::Path, position: three::Point) -> Result<Model, Box<dyn error::Error>> {
+        load_obj_colored(&path.to_string_lossy(), self.max_edges, position, self.preview.as_ref()).map_err(|error| {
+            if !error.is::<EmptyObj>() {
+                return error;
+            }
+            let subject = match (&self.chain, &self.select) {
+                (Some(chain), _) => format!("Chain {}", chain),
+                (None, Some(select)) => format!("The selection \"{}\"", select),
+                (None, None) => "The structure".to_string(),
+            };
+            let message = match self.representation {
+                Representation::Cartoon | Representation::Ribbon | Representation::Backbone => format!(
+                    "{} has no polymer {}; try --repr sticks to see its ligands and other atoms.",
+                    subject, self.representation.name()
+                ),
+                _ => format!("{} has no atoms to draw as {}; check --chain and --select.", subject, self.representation.name()),
+            };
+            Box::new(ParseError(message)) as Box<dyn error::Error>
+        })
+    }
+
+    // PyMOL python lines that write the `--center` residue's CA position.
+    // The residue is checked first, as it is pasted into the script.
+    fn center_commands(&self, obj_path: &path::Path) -> Result<String, Box<dyn error::Error>> {
+        match (&self.center, self.center_path(obj_path)) {
+            (Some((chain, resi)), Some(path)) => {
+                check_center(chain, resi).map_err(ParseError)?;
+                Ok(format!(
+                    "xyz = cmd.get_coords('chain \"{}\" and resi {} and name CA')\n\
+if xyz is not None:\n    open(r\"{}\", \"w\").write(\"%f %f %f\" % tuple(xyz[0]))",
+                    chain, resi, path.display()
+                ))
+            }
+            _ => Ok(String::new()),
+        }
+    }
+
+    // Read back the `--center` residue position written by PyMOL.
+    fn read_center(&self, obj_path: &path::Path) -> Result<Option<three::Point>, Box<dyn error::Error>> {
+        let (Some((chain, resi)), Some(path)) = (&self.center, self.center_path(obj_path)) else {
+            return Ok(None);
+        };
+        let missing = || ParseError(format!("Residue {}/{} has no CA atom in this structure.", chain, resi));
+
+        let text = fs::read_to_string(path).map_err(|_| missing())?;
+        let coords: Vec<f32> = text.split_whitespace().filter_map(|v| v.parse().ok()).collect();
+        match coords[..] {
+            [x, y, z] => Ok(Some(three::Point::new(x, y, z))),
+            _ => Err(Box::new(missing())),
+        }
+    }
+
+    // `save_by_chain`'s `disulfides` argument.
+    fn pymol_disulfides(&self) -> &'static str {
+        if self.disulfides { "True" } else { "False" }
+    }
+
+    // `save_by_chain`'s `hbonds` argument.
+    fn pymol_hbonds(&self) -> &'static str {
+        if self.hbonds { "True" } else { "False" }
+    }
+
+    // PyMOL selection for the requested `--select` expression and chain. The
+    // chain ID is quoted, escaped for the python string it is pasted into, so
+    // lowercase and multi-character IDs match verbatim.
+    fn selection(&self) -> String {
+        match (&self.select, &self.chain) {
+            (Some(s), Some(c)) => format!("({}) and chain \\\"{}\\\"", s, c),
+            (Some(s), None) => format!("({})", s),
+            (None, Some(c)) => format!("chain \\\"{}\\\"", c),
+            (None, None) => "all".to_string(),
+        }
+    }
+}
+
+// Check a `--select` expression before it is pasted into PyMOL scripts: it
+// must stay on one line, inside a python string, and not end the session.
+pub fn check_selection(selection: &str) -> Result<(), String> {
+    if selection.trim().is_empty() {
+        return Err("--select requires a non-empty PyMOL selection (e.g., \"resi 1-50 and chain A\").".to_string());
+    }
+    if let Some(c) = selection.chars().find(|c| matches!(c, '\n' | '\r' | ';' | '"' | '\\')) {
+        return Err(format!("--select may not contain {:?}.", c));
+    }
+    if selection.split(|c: char| !c.is_ascii_alphanumeric() && c != '_').any(|word| word.eq_ignore_ascii_case("quit")) {
+        return Err("--select may not contain 'quit'.".to_string());
+    }
+    Ok(())
+}
+
+// Reject a `--center` residue that could break out of the PyMOL script it is
+// pasted into: the chain must be letters and digits, the residue number an
+// integer with an optional sign and insertion code (e.g. 57, -3, 52A).
+pub fn check_center(chain: &str, resi: &str) -> Result<(), String> {
+    if chain.is_empty() || !chain.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(format!("--center chain must be letters and digits, not '{}'.", chain));
+    }
+    let number = resi.strip_prefix(['-', '+']).unwrap_or(resi);
+    let digits = number.strip_suffix(|c: char| c.is_ascii_alphabetic()).unwrap_or(number);
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(format!("--center residue must be a number with an optional insertion code (e.g., 57 or 52A), not '{}'.", resi));
+    }
+    Ok(())
+}
+
+// A chain ID as it appears in cache file names. Chain IDs are case-sensitive
+// (`a` and `A` are different chains in large assemblies) but file systems may
+// not be, so IDs other than uppercase letters and digits get a digest.
+fn chain_file_tag(chain: &str) -> String {
+    if chain.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()) {
+        return chain.to_string();
+    }
+    let safe: String = chain.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    format!("{}-{:08x}", safe, fnv1a(chain.as_bytes()))
+}
+
+// 32-bit FNV-1a hash, for short cache-name digests that are stable across builds.
+fn fnv1a(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c9dc5u32, |h, &b| (h ^ b as u32).wrapping_mul(0x01000193))
+}
+
+// PyMOL python helper that saves a selection as OBJ with one `g chain_<ID>`
+// group per chain, so the loader can tell chains apart. Each group starts with
+// `#ca <resi> x y z b ss` comments locating its residues, their B-factors
+// and secondary structure. Sticks and surfaces are exported one element at a
+// time, each part after a `usemtl <symbol>` line for coloring by element
+// (cartoons and ribbons only follow carbons, so they are left whole).
+// With `disulfides`, sticks for SG-SG bonds follow in a `g disulfides` group.
+// With `hbonds`, polar contacts (what `distance mode=2` shows) follow as one
+// `l` segment per donor-acceptor pair in a `g hbonds` group.
+const PYMOL_SAVE_BY_CHAIN: &str = r##"from pymol import cmd
+def append_mesh(lines, offset):
+    count = 0
+    for line in cmd.get_mtl_obj()[1].splitlines():
+        parts = line.split()
+        if not parts or parts[0] in ("vn", "g", "o", "mtllib", "usemtl"):
+            continue
+        if parts[0] == "v":
+            count += 1
+            lines.append(line)
+        elif parts[0] == "f":
+            lines.append("f " + " ".join(str(int(p.split("/")[0]) + offset) for p in parts[1:]))
+        else:
+            lines.append(line)
+    return offset + count
+def append_shown(lines, offset, representation, shown):
+    if representation not in ("sticks", "surface"):
+        cmd.show(representation, shown)
+        return append_mesh(lines, offset)
+    # Half bonds give each element its own end of a bond between two.
+    cmd.set("half_bonds", 1)
+    for symbol in sorted(set(atom.symbol for atom in cmd.get_model(shown).atom)):
+        cmd.hide("everything")
+        cmd.show(representation, "(%s) and elem %s" % (shown, symbol))
+        lines.append("usemtl %s" % symbol)
+        offset = append_mesh(lines, offset)
+    return offset
+def save_by_chain(selection, representation, path, disulfides=False, hbonds=False):
+    lines = []
+    offset = 0
+    for chain in cmd.get_chains(selection):
+        cmd.hide("everything")
+        lines.append("g chain_%s" % chain)
+        for atom in cmd.get_model('(%s) and chain "%s" and name CA' % (selection, chain)).atom:
+            lines.append("#ca %s %f %f %f %f %s" % (atom.resi, atom.coord[0], atom.coord[1], atom.coord[2], atom.b, atom.ss or "L"))
+        offset = append_shown(lines, offset, representation, '(%s) and chain "%s"' % (selection, chain))
+    if disulfides:
+        cmd.hide("everything")
+        cmd.show("sticks", "(%s) and name SG and bound_to name SG" % selection)
+        lines.append("g disulfides")
+        offset = append_mesh(lines, offset)
+    if hbonds:
+        polar = "(%s) and (donor or acceptor)" % selection
+        lines.append("g hbonds")
+        seen = set()
+        for a, b in cmd.find_pairs(polar, polar, mode=1, cutoff=3.6):
+            if (b, a) in seen:
+                continue
+            seen.add((a, b))
+            for atom in (a, b):
+                lines.append("v %f %f %f" % tuple(cmd.get_coords("%s and index %d" % atom)[0]))
+            lines.append("l %d %d" % (offset + 1, offset + 2))
+            offset += 2
+    with open(path, "w") as f:
+        f.write("\n".join(lines) + "\n")
+"##;
+
+const DEFAULT_MAX_EDGES: usize = 50000;
+
+// Whether an exported OBJ holds geometry, or names chains but holds nothing
+// else, as `save_by_chain` writes for chains without the representation. A
+// structure that failed to load has no chains, so its OBJ is blank instead.
+// Streams the file and stops at the first vertex or face.
+fn is_usable_export<R: io::BufRead>(reader: R) -> bool {
+    let mut names_group = false;
+    let mut only_names = true;
+    for line in obj_lines(reader) {
+        let Ok(line) = line else {
+            return false;
+        };
+        match line.split_whitespace().next() {
+            Some("v" | "f") => return true,
+            Some(keyword) => {
+                names_group |= keyword == "g";
+                only_names &= is_naming_keyword(keyword);
+            }
+            None => {}
         }
     }
+    names_group && only_names
+}
+
+// Whether an OBJ line keyword only names or styles geometry, or is a comment.
+fn is_naming_keyword(keyword: &str) -> bool {
+    keyword.starts_with('#') || ["g", "o", "mtllib", "usemtl"].contains(&keyword)
+}
+
+// Logical lines of an OBJ file, read one at a time so huge surfaces are never
+// held in memory twice. A backslash just before a line break continues the
+// line, the two joined by a space.
+fn obj_lines<R: io::BufRead>(mut reader: R) -> impl Iterator<Item = io::Result<String>> {
+    let mut done = false;
+    iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        let mut line = String::new();
+        loop {
+            match reader.read_line(&mut line) {
+                Ok(0) => {
+                    done = true;
+                    return Some(Ok(line));
+                }
+                Ok(_) if line.ends_with("\\\n") => {
+                    line.truncate(line.len() - 2);
+                    line.push(' ');
+                }
+                Ok(_) => {
+                    if line.ends_with('\n') {
+                        line.pop();
+                    }
+                    return Some(Ok(line));
+                }
+                Err(e) => {
+                    done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    })
 }
 
-fn load_obj_colored(path: &str, position: three::Point) -> Result<Model, Box<dyn error::Error>> {
-    let mut code = fs::read_to_string(path)?;
-    code = code.replace("\\\n", " ");
+// A `#ca` comment: chain, position, residue number, B-factor and secondary structure.
+type CaRecord = (Option<String>, three::Point, i32, Option<f32>, Option<SecondaryStructure>);
+
+// With `preview`, the edges of faces and lines are also sent in chunks as
+// they are read, before the model is built from all of them.
+fn load_obj_colored(path: &str, max_edges: usize, position: three::Point, preview: Option<&sync::mpsc::Sender<EdgeChunk>>) -> Result<Model, Box<dyn error::Error>> {
+    let reader = io::BufReader::new(fs::File::open(path)?);
 
     let mut vertices = Vec::<three::Point>::new();
     let mut faces = Vec::<Vec<usize>>::new();
-
-    for line in code.split('\n') {
+    let mut vertex_elements = Vec::<Option<Element>>::new();
+    let mut current_element: Option<Element> = None;
+    let mut vertex_chains = Vec::<Option<String>>::new();
+    let mut current_chain: Option<String> = None;
+    let mut vertex_fixed_colors = Vec::<Option<Rgb>>::new();
+    let mut vertex_dashed = Vec::<bool>::new();
+    let mut vertex_colors = Vec::<Option<Rgb>>::new();
+    let mut in_disulfides = false;
+    let mut in_hbonds = false;
+    let mut ca_atoms = Vec::<CaRecord>::new();
+    // What `is_usable_export` checks for an empty export, gathered while streaming.
+    let mut names_group = false;
+    let mut only_names = true;
+    let mut pending = EdgeChunk::new();
+
+    for line in obj_lines(reader) {
+        let line = line?;
         let mut tokens = line.split_whitespace().filter(|&s| !s.is_empty());
+        let keyword = tokens.next();
+        if let Some(keyword) = keyword {
+            names_group |= keyword == "g";
+            only_names &= is_naming_keyword(keyword);
+        }
 
-        match tokens.next() {
+        match keyword {
             Some("v") => {
                 let coords: Vec<&str> = tokens.collect();
                 if coords.len() >= 3 {
@@ -122,9 +932,26 @@ fn load_obj_colored(path: &str, position: three::Point) -> Result<Model, Box<dyn
                     let y = coords[1].parse::<f32>()?;
                     let z = coords[2].parse::<f32>()?;
                     vertices.push(three::Point::new(x, y, z));
+                    vertex_chains.push(current_chain.clone());
+                    vertex_fixed_colors.push(if in_disulfides {
+                        Some(DISULFIDE_COLOR)
+                    } else {
+                        in_hbonds.then_some(HBOND_COLOR)
+                    });
+                    vertex_dashed.push(in_hbonds);
+                    // Optional `r g b` after the position, each 0 to 1.
+                    let channels: Vec<f32> = coords[3..].iter().filter_map(|c| c.parse().ok()).collect();
+                    vertex_colors.push(match channels[..] {
+                        [r, g, b] => Some(Rgb::new(
+                            (r.clamp(0., 1.) * 255.).round() as u8,
+                            (g.clamp(0., 1.) * 255.).round() as u8,
+                            (b.clamp(0., 1.) * 255.).round() as u8,
+                        )),
+                        _ => None,
+                    });
                 }
             }
-            Some("f") | Some("fo") => {
+            Some(kind @ ("f" | "fo" | "l")) => {
                 let mut face = Vec::<usize>::new();
                 for point in tokens {
                     if let Some(vertex_str) = point.split('/').next() {
@@ -136,14 +963,69 @@ fn load_obj_colored(path: &str, position: three::Point) -> Result<Model, Box<dyn
                     }
                 }
                 if face.len() >= 2 {
-                    faces.push(face);
+                    if current_element.is_some() {
+                        for &idx in &face {
+                            if idx >= vertex_elements.len() {
+                                vertex_elements.resize(idx + 1, None);
+                            }
+                            vertex_elements[idx] = current_element;
+                        }
+                    }
+                    if let Some(preview) = preview {
+                        let closing = if kind == "l" { None } else { Some((face[face.len() - 1], face[0])) };
+                        pending.extend(face.windows(2).map(|pair| (pair[0], pair[1])).chain(closing)
+                            .filter_map(|(a, b)| Some((*vertices.get(a)?, *vertices.get(b)?))));
+                        if pending.len() >= PREVIEW_CHUNK_EDGES {
+                            let _ = preview.send(mem::take(&mut pending));
+                        }
+                    }
+                    // Line elements are open polylines (e.g. from --dump-obj), so split
+                    // them into segments rather than closing them like a face.
+                    if kind == "l" {
+                        faces.extend(face.windows(2).map(|pair| pair.to_vec()));
+                    } else {
+                        faces.push(face);
+                    }
+                }
+            }
+            Some("usemtl") => {
+                current_element = tokens.next().and_then(Element::from_material);
+            }
+            Some("#ca") => {
+                let fields: Vec<&str> = tokens.collect();
+                // Caches written before B-factors or secondary structure were
+                // exported lack the last fields.
+                if let [resi, x, y, z, ..] = fields[..] {
+                    // Insertion codes (e.g. `52A`) share their residue number.
+                    let number: String = resi.chars().take_while(|c| *c == '-' || c.is_ascii_digit()).collect();
+                    let bfactor = fields.get(4).and_then(|b| b.parse().ok());
+                    let ss = fields.get(5).map(|code| SecondaryStructure::from_pymol(code));
+                    if let (Ok(resi), Ok(x), Ok(y), Ok(z)) = (number.parse(), x.parse(), y.parse(), z.parse()) {
+                        ca_atoms.push((current_chain.clone(), three::Point::new(x, y, z), resi, bfactor, ss));
+                    }
                 }
             }
+            Some("g") | Some("o") => {
+                let name = tokens.next();
+                in_disulfides = name == Some("disulfides");
+                in_hbonds = name == Some("hbonds");
+                current_chain = name
+                    .and_then(|name| name.strip_prefix("chain_"))
+                    .filter(|chain| !chain.is_empty())
+                    .map(String::from);
+            }
             _ => {}
         }
     }
 
+    if let Some(preview) = preview.filter(|_| !pending.is_empty()) {
+        let _ = preview.send(pending);
+    }
+
     if vertices.is_empty() {
+        if names_group && only_names {
+            return Err(Box::new(EmptyObj));
+        }
         return Err(Box::new(ParseError("No vertices found in OBJ".to_string())));
     }
 
@@ -156,6 +1038,10 @@ fn load_obj_colored(path: &str, position: three::Point) -> Result<Model, Box<dyn
         }
     }
     let idx_range = if max_idx > min_idx { max_idx - min_idx } else { 1 };
+    let vertex_ca = nearest_ca_atoms(&vertices, &vertex_chains, &ca_atoms);
+    let vertex_resi: Vec<Option<i32>> = vertex_ca.iter().map(|ca| ca.map(|i| ca_atoms[i].2)).collect();
+    let vertex_bfactor: Vec<Option<f32>> = vertex_ca.iter().map(|ca| ca.and_then(|i| ca_atoms[i].3)).collect();
+    let vertex_ss: Vec<Option<SecondaryStructure>> = vertex_ca.iter().map(|ca| ca.and_then(|i| ca_atoms[i].4)).collect();
     let mut colored_edges: Vec<ColoredEdge> = Vec::new();
 
     for face in &faces {
@@ -165,6 +1051,9 @@ fn load_obj_colored(path: &str, position: three::Point) -> Result<Model, Box<dyn
                 let end_idx = face[(i + 1) % face.len()];
 
                 if start_idx < vertices.len() && end_idx < vertices.len() {
+                    // Store edges in index order so shared triangle edges (a->b and
+                    // b->a) become identical and are removed by the dedup below.
+                    let (start_idx, end_idx) = (start_idx.min(end_idx), start_idx.max(end_idx));
                     let t1 = (start_idx - min_idx) as f32 / idx_range as f32;
                     let t2 = (end_idx - min_idx) as f32 / idx_range as f32;
 
@@ -175,6 +1064,19 @@ fn load_obj_colored(path: &str, position: three::Point) -> Result<Model, Box<dyn
                         end_color: Rgb::white(),
                         start_t: t1,
                         end_t: t2,
+                        start_element: vertex_elements.get(start_idx).copied().flatten(),
+                        end_element: vertex_elements.get(end_idx).copied().flatten(),
+                        chain_id: vertex_chains[start_idx].clone(),
+                        start_resi: vertex_resi[start_idx],
+                        end_resi: vertex_resi[end_idx],
+                        start_bfactor: vertex_bfactor[start_idx],
+                        end_bfactor: vertex_bfactor[end_idx],
+                        start_ss: vertex_ss[start_idx],
+                        end_ss: vertex_ss[end_idx],
+                        start_vertex_color: vertex_colors[start_idx],
+                        end_vertex_color: vertex_colors[end_idx],
+                        fixed_color: vertex_fixed_colors[start_idx],
+                        dashed: vertex_dashed[start_idx],
                     });
                 }
             }
@@ -217,14 +1119,14 @@ fn load_obj_colored(path: &str, position: three::Point) -> Result<Model, Box<dyn
         dx * dx + dy * dy + dz * dz >= MIN_EDGE_LENGTH * MIN_EDGE_LENGTH
     });
 
-    const MAX_EDGES: usize = 50000;
-    if colored_edges.len() > MAX_EDGES {
-        let step = (colored_edges.len() as f32 / MAX_EDGES as f32).ceil() as usize;
-        colored_edges = colored_edges.into_iter()
-            .enumerate()
-            .filter(|(i, _)| i % step == 0)
-            .map(|(_, e)| e)
-            .collect();
+    // Drop the shortest edges first; long ones carry the overall shape.
+    // Bonds drawn in a fixed color are kept ahead of the rest.
+    if colored_edges.len() > max_edges {
+        let length = |e: &ColoredEdge| e.start.distance(&e.end);
+        colored_edges.select_nth_unstable_by(max_edges, |a, b| {
+            b.fixed_color.is_some().cmp(&a.fixed_color.is_some()).then(length(b).total_cmp(&length(a)))
+        });
+        colored_edges.truncate(max_edges);
     }
 
     Ok(Model {
@@ -232,191 +1134,1458 @@ fn load_obj_colored(path: &str, position: three::Point) -> Result<Model, Box<dyn
         edges: Vec::new(),
         colored_edges,
         position,
+        note: None,
+        residues: ca_atoms.iter().map(|(_, point, ..)| *point).collect(),
+        residue_ids: ca_atoms.iter().map(|(chain, _, resi, ..)| (chain.clone(), *resi)).collect(),
     })
 }
 
-fn get_cache_dir() -> Result<path::PathBuf, Box<dyn error::Error>> {
-    let home = env::var("HOME").map_err(|_| ParseError("HOME not set".to_string()))?;
-    let cache_dir = path::PathBuf::from(home).join(".cache").join("pepterm");
-    if !cache_dir.exists() {
-        fs::create_dir_all(&cache_dir)?;
-    }
-    Ok(cache_dir)
+// Slack added to the sum of covalent radii when deciding two atoms are bonded.
+const BOND_TOLERANCE: f32 = 0.4;
+
+pub fn is_xyz(input: &str) -> bool {
+    input.to_lowercase().ends_with(".xyz")
 }
 
-pub fn cache_info() -> Result<(usize, u64, path::PathBuf), Box<dyn error::Error>> {
-    let cache_dir = get_cache_dir()?;
-    let mut count = 0;
-    let mut total_size = 0u64;
+// Load every frame of an `.xyz` file (atom count, comment, then `El x y z`
+// lines, repeated per frame), inferring bonds from covalent radii.
+fn load_xyz(path: &str, position: three::Point) -> Result<Vec<Model>, Box<dyn error::Error>> {
+    let code = fs::read_to_string(path)?;
+    let mut lines = code.lines();
+    let mut frames = Vec::new();
 
-    if cache_dir.exists() {
-        for entry in fs::read_dir(&cache_dir)? {
-            let entry = entry?;
-            let metadata = entry.metadata()?;
-            if metadata.is_file() {
-                count += 1;
-                total_size += metadata.len();
+    while let Some(count_line) = lines.next() {
+        if count_line.trim().is_empty() {
+            continue;
+        }
+        let count = count_line.trim().parse::<usize>()
+            .map_err(|_| ParseError(format!("Expected an atom count in {}, found '{}'", path, count_line.trim())))?;
+        lines.next(); // comment line
+
+        let mut atoms = Vec::with_capacity(count);
+        for _ in 0..count {
+            let line = lines.next()
+                .ok_or_else(|| ParseError(format!("{} ends in the middle of a frame", path)))?;
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            if tokens.len() < 4 {
+                return Err(Box::new(ParseError(format!("Invalid atom line in {}: '{}'", path, line))));
             }
+            let point = three::Point::new(tokens[1].parse()?, tokens[2].parse()?, tokens[3].parse()?);
+            atoms.push((point, Element::from_material(tokens[0]).unwrap_or(Element::Other)));
         }
+
+        frames.push(xyz_frame_to_model(&atoms, position));
     }
 
-    Ok((count, total_size, cache_dir))
+    if frames.is_empty() {
+        return Err(Box::new(ParseError(format!("No atoms found in {}", path))));
+    }
+    Ok(frames)
 }
 
-pub fn cache_clear() -> Result<usize, Box<dyn error::Error>> {
-    let cache_dir = get_cache_dir()?;
-    let mut count = 0;
+// Bond atoms closer than their covalent radii allow, using a grid so only
+// neighbouring cells are compared.
+fn xyz_frame_to_model(atoms: &[(three::Point, Element)], position: three::Point) -> Model {
+    let cell_size = 2. * Element::Other.covalent_radius() + BOND_TOLERANCE;
+    let cell_of = |p: &three::Point| (
+        (p.x / cell_size).floor() as i32,
+        (p.y / cell_size).floor() as i32,
+        (p.z / cell_size).floor() as i32,
+    );
 
-    if cache_dir.exists() {
-        for entry in fs::read_dir(&cache_dir)? {
-            let entry = entry?;
-            if entry.metadata()?.is_file() {
-                fs::remove_file(entry.path())?;
-                count += 1;
+    let mut grid: collections::HashMap<(i32, i32, i32), Vec<usize>> = collections::HashMap::new();
+    for (i, (point, _)) in atoms.iter().enumerate() {
+        grid.entry(cell_of(point)).or_default().push(i);
+    }
+
+    let last = atoms.len().saturating_sub(1).max(1) as f32;
+    let mut colored_edges = Vec::new();
+
+    for (i, (a, a_element)) in atoms.iter().enumerate() {
+        let (cx, cy, cz) = cell_of(a);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let Some(neighbours) = grid.get(&(cx + dx, cy + dy, cz + dz)) else { continue };
+                    for &j in neighbours.iter().filter(|&&j| j > i) {
+                        let (b, b_element) = &atoms[j];
+                        let max_length = a_element.covalent_radius() + b_element.covalent_radius() + BOND_TOLERANCE;
+                        if a.distance(b) > max_length {
+                            continue;
+                        }
+                        colored_edges.push(ColoredEdge {
+                            start: *a,
+                            end: *b,
+                            start_color: Rgb::white(),
+                            end_color: Rgb::white(),
+                            start_t: i as f32 / last,
+                            end_t: j as f32 / last,
+                            start_element: Some(*a_element),
+                            end_element: Some(*b_element),
+                            chain_id: None,
+                            start_resi: None,
+                            end_resi: None,
+                            start_bfactor: None,
+                            end_bfactor: None,
+                            start_ss: None,
+                            end_ss: None,
+                            start_vertex_color: None,
+                            end_vertex_color: None,
+                            fixed_color: None,
+                            dashed: false,
+                        });
+                    }
+                }
             }
         }
     }
 
-    Ok(count)
+    Model {
+        points: atoms.iter().map(|(p, _)| *p).collect(),
+        edges: Vec::new(),
+        colored_edges,
+        position,
+        note: None,
+        residues: Vec::new(),
+        residue_ids: Vec::new(),
+    }
 }
 
-fn check_pymol() -> Result<(), Box<dyn error::Error>> {
-    let pymol_check = Command::new("which").arg("pymol").output();
-    if pymol_check.is_err() || !pymol_check.unwrap().status.success() {
-        return Err(Box::new(ParseError(
+// A CA atom read natively from a PDB or mmCIF file.
+struct CaAtom {
+    chain: String,
+    resi: i32,
+    point: three::Point,
+    bfactor: f32,
+}
+
+// Longest CA-CA distance still treated as a peptide bond; longer gaps are
+// missing residues and break the trace.
+const MAX_CA_GAP: f32 = 4.5;
+// Length of the perpendicular rungs drawn by the tube representation, in Å.
+const RUNG_LENGTH: f32 = 1.5;
+
+// Download an RCSB entry as mmCIF into the cache (named like PyMOL's fetch,
+// so both share it), returning its path.
+fn fetch_structure(pdb_id: &str, options: &LoadOptions) -> Result<String, Box<dyn error::Error>> {
+    let cif_path = get_cache_dir()?.join(format!("{}.cif", pdb_id.to_lowercase()));
+    if options.cache_hit(&cif_path) {
+        return Ok(cif_path.to_string_lossy().to_string());
+    }
+
+    check_obsolete(&pdb_id.to_uppercase())?;
+    progress!("Fetching {} from RCSB PDB...", pdb_id.to_uppercase());
+    let url = format!("https://files.rcsb.org/download/{}.cif", pdb_id.to_uppercase());
+    let output = curl(&["-sfL", "-o", &cif_path.to_string_lossy(), &url])?;
+
+    if !output.status.success() {
+        let _ = fs::remove_file(&cif_path);
+        return Err(Box::new(ParseError(format!("Could not download {}. Check PDB ID.", pdb_id))));
+    }
+    Ok(cif_path.to_string_lossy().to_string())
+}
+
+// CA atoms of every model in a PDB or mmCIF file, keeping the first
+// alternate location and the requested chain only.
+fn read_ca_models(path: &str, chain: Option<&str>) -> Result<Vec<Vec<CaAtom>>, Box<dyn error::Error>> {
+    let text = fs::read_to_string(path)?;
+    let mut models = if path.to_lowercase().ends_with(".cif") {
+        parse_cif_ca(&text)
+    } else {
+        parse_pdb_ca(&text)
+    };
+
+    if let Some(chain) = chain {
+        for atoms in &mut models {
+            atoms.retain(|atom| atom.chain == chain);
+        }
+    }
+    models.retain(|atoms| !atoms.is_empty());
+    if models.is_empty() {
+        return Err(Box::new(ParseError(match chain {
+            Some(chain) => format!("Chain {} has no CA atoms in {}", chain, path),
+            None => format!("No CA atoms found in {}", path),
+        })));
+    }
+    Ok(models)
+}
+
+// Fixed-column `ATOM` records, split into models at `ENDMDL`.
+fn parse_pdb_ca(text: &str) -> Vec<Vec<CaAtom>> {
+    let mut models = vec![Vec::new()];
+    for line in text.lines() {
+        if line.starts_with("ENDMDL") {
+            models.push(Vec::new());
+            continue;
+        }
+        if !line.starts_with("ATOM") || line.len() < 54 || line.get(12..16).map(str::trim) != Some("CA") {
+            continue;
+        }
+        if !matches!(&line[16..17], " " | "A") {
+            continue;
+        }
+
+        let field = |range: ops::Range<usize>| line.get(range).map(str::trim).unwrap_or("");
+        let (Ok(resi), Ok(x), Ok(y), Ok(z)) = (
+            field(22..26).parse(), field(30..38).parse(), field(38..46).parse(), field(46..54).parse(),
+        ) else { continue };
+        models.last_mut().unwrap().push(CaAtom {
+            chain: field(21..22).to_string(),
+            resi,
+            point: three::Point::new(x, y, z),
+            bfactor: field(60..66).parse().unwrap_or(0.),
+        });
+    }
+    models
+}
+
+// Rows of the mmCIF `_atom_site` loop, split into models by model number.
+fn parse_cif_ca(text: &str) -> Vec<Vec<CaAtom>> {
+    let mut columns: Vec<&str> = Vec::new();
+    let mut models: Vec<(String, Vec<CaAtom>)> = Vec::new();
+    let mut lines = text.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        if line.trim() != "loop_" || !lines.peek().is_some_and(|l| l.starts_with("_atom_site.")) {
+            continue;
+        }
+        while let Some(header) = lines.next_if(|l| l.starts_with("_atom_site.")) {
+            columns.push(header.trim().trim_start_matches("_atom_site."));
+        }
+        let column = |names: &[&str]| names.iter().find_map(|name| columns.iter().position(|c| c == name));
+        let (Some(group), Some(atom), Some(chain), Some(resi), Some(x), Some(y), Some(z)) = (
+            column(&["group_PDB"]),
+            column(&["label_atom_id", "auth_atom_id"]),
+            column(&["auth_asym_id", "label_asym_id"]),
+            column(&["auth_seq_id", "label_seq_id"]),
+            column(&["Cartn_x"]),
+            column(&["Cartn_y"]),
+            column(&["Cartn_z"]),
+        ) else { break };
+        let (alt, bfactor, model) = (column(&["label_alt_id"]), column(&["B_iso_or_equiv"]), column(&["pdbx_PDB_model_num"]));
+
+        while let Some(row) = lines.next_if(|l| !l.starts_with('#') && !l.starts_with('_') && l.trim() != "loop_") {
+            let fields = cif_tokens(row);
+            if fields.len() < columns.len() || fields[group] != "ATOM" || fields[atom] != "CA" {
+                continue;
+            }
+            if alt.is_some_and(|a| !matches!(fields[a], "." | "?" | "A")) {
+                continue;
+            }
+            let (Ok(resi), Ok(x), Ok(y), Ok(z)) = (
+                fields[resi].parse(), fields[x].parse(), fields[y].parse(), fields[z].parse(),
+            ) else { continue };
+
+            let model_id = model.map_or("1", |m| fields[m]);
+            if models.last().is_none_or(|(id, _)| id != model_id) {
+                models.push((model_id.to_string(), Vec::new()));
+            }
+            models.last_mut().unwrap().1.push(CaAtom {
+                chain: fields[chain].to_string(),
+                resi,
+                point: three::Point::new(x, y, z),
+                bfactor: bfactor.and_then(|b| fields[b].parse().ok()).unwrap_or(0.),
+            });
+        }
+        break;
+    }
+    models.into_iter().map(|(_, atoms)| atoms).collect()
+}
+
+// Split an mmCIF data row on whitespace, keeping quoted values together.
+fn cif_tokens(row: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut rest = row.trim_start();
+    while !rest.is_empty() {
+        let quote = rest.chars().next().filter(|c| *c == '\'' || *c == '"');
+        let end = match quote {
+            Some(q) => rest[1..].find(q).map_or(rest.len(), |i| i + 2),
+            None => rest.find(char::is_whitespace).unwrap_or(rest.len()),
+        };
+        tokens.push(rest[..end].trim_matches(|c| Some(c) == quote));
+        rest = rest[end..].trim_start();
+    }
+    tokens
+}
+
+// Connect consecutive CA atoms of each chain. With `rungs`, a short segment
+// across every residue, along the backbone's curvature, suggests thickness.
+fn trace_model(atoms: &[CaAtom], rungs: bool, position: three::Point) -> Model {
+    let last = atoms.len().saturating_sub(1).max(1) as f32;
+    let bonded = |a: &CaAtom, b: &CaAtom| a.chain == b.chain && a.point.distance(&b.point) <= MAX_CA_GAP;
+    let edge = |start: three::Point, end: three::Point, a: (usize, &CaAtom), b: (usize, &CaAtom)| ColoredEdge {
+        start,
+        end,
+        start_color: Rgb::white(),
+        end_color: Rgb::white(),
+        start_t: a.0 as f32 / last,
+        end_t: b.0 as f32 / last,
+        start_element: None,
+        end_element: None,
+        chain_id: Some(a.1.chain.clone()),
+        start_resi: Some(a.1.resi),
+        end_resi: Some(b.1.resi),
+        start_bfactor: Some(a.1.bfactor),
+        end_bfactor: Some(b.1.bfactor),
+        start_ss: None,
+        end_ss: None,
+        start_vertex_color: None,
+        end_vertex_color: None,
+        fixed_color: None,
+        dashed: false,
+    };
+
+    let mut colored_edges = Vec::new();
+    for (i, pair) in atoms.windows(2).enumerate() {
+        if bonded(&pair[0], &pair[1]) {
+            colored_edges.push(edge(pair[0].point, pair[1].point, (i, &pair[0]), (i + 1, &pair[1])));
+        }
+    }
+
+    if rungs {
+        for (i, triple) in atoms.windows(3).enumerate() {
+            let [prev, atom, next] = triple else { continue };
+            if !bonded(prev, atom) || !bonded(atom, next) {
+                continue;
+            }
+            // Points toward the inside of the bend, e.g. a helix axis.
+            let normal = three::Point::new(
+                prev.point.x + next.point.x - 2. * atom.point.x,
+                prev.point.y + next.point.y - 2. * atom.point.y,
+                prev.point.z + next.point.z - 2. * atom.point.z,
+            );
+            let length = normal.distance(&three::Point::new(0., 0., 0.));
+            if length < f32::EPSILON {
+                continue;
+            }
+            let scale = RUNG_LENGTH / 2. / length;
+            let offset = |sign: f32| three::Point::new(
+                atom.point.x + sign * normal.x * scale,
+                atom.point.y + sign * normal.y * scale,
+                atom.point.z + sign * normal.z * scale,
+            );
+            colored_edges.push(edge(offset(-1.), offset(1.), (i + 1, atom), (i + 1, atom)));
+        }
+    }
+
+    Model {
+        points: atoms.iter().map(|atom| atom.point).collect(),
+        edges: Vec::new(),
+        colored_edges,
+        position,
+        note: None,
+        residues: atoms.iter().map(|atom| atom.point).collect(),
+        residue_ids: atoms.iter().map(|atom| (Some(atom.chain.clone()), atom.resi)).collect(),
+    }
+}
+
+// Build CA traces (one model per state) for a PDB ID, `af:` accession or
+// PDB/CIF file without PyMOL, along with the `--center` residue position.
+fn load_trace(input: &str, options: &LoadOptions, position: three::Point) -> Result<(Vec<Model>, Option<three::Point>), Box<dyn error::Error>> {
+    let path = if let Some(accession) = alphafold_accession(input) {
+        fetch_alphafold(&accession, options)?
+    } else if is_structure_file(input) {
+        input.to_string()
+    } else {
+        fetch_structure(input, options)?
+    };
+    if options.select.is_some() {
+        eprintln!("Warning: --select needs PyMOL and is ignored for CA traces.");
+    }
+
+    let rungs = options.representation == Representation::Tube;
+    let states = read_ca_models(&path, options.chain.as_deref())?;
+    let center = match &options.center {
+        Some((chain, resi)) => Some(states[0].iter()
+            .find(|atom| &atom.chain == chain && atom.resi.to_string() == *resi)
+            .map(|atom| atom.point)
+            .ok_or_else(|| ParseError(format!("Residue {}/{} has no CA atom in this structure.", chain, resi)))?),
+        None => None,
+    };
+    Ok((states.iter().map(|atoms| trace_model(atoms, rungs, position)).collect(), center))
+}
+
+// Index of the nearest CA atom in the same chain, per vertex. CA atoms are
+// bucketed into a grid so each vertex only checks nearby cells.
+fn nearest_ca_atoms(
+    vertices: &[three::Point],
+    vertex_chains: &[Option<String>],
+    ca_atoms: &[CaRecord],
+) -> Vec<Option<usize>> {
+    const CELL_SIZE: f32 = 8.0; // Å, a bit over two residues along the backbone
+    if ca_atoms.is_empty() {
+        return vec![None; vertices.len()];
+    }
+
+    let cell_of = |p: &three::Point| (
+        (p.x / CELL_SIZE).floor() as i32,
+        (p.y / CELL_SIZE).floor() as i32,
+        (p.z / CELL_SIZE).floor() as i32,
+    );
+    let mut grid: collections::HashMap<(i32, i32, i32), Vec<usize>> = collections::HashMap::new();
+    for (i, (_, point, ..)) in ca_atoms.iter().enumerate() {
+        grid.entry(cell_of(point)).or_default().push(i);
+    }
+
+    vertices.iter().zip(vertex_chains).map(|(vertex, chain)| {
+        let (cx, cy, cz) = cell_of(vertex);
+        let mut best: Option<(f32, usize)> = None;
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let Some(candidates) = grid.get(&(cx + dx, cy + dy, cz + dz)) else { continue };
+                    for &i in candidates {
+                        let (ca_chain, point, ..) = &ca_atoms[i];
+                        if ca_chain != chain {
+                            continue;
+                        }
+                        let distance = vertex.distance(point);
+                        if best.is_none_or(|(d, _)| distance < d) {
+                            best = Some((distance, i));
+                        }
+                    }
+                }
+            }
+        }
+        best.map(|(_, i)| i)
+    }).collect()
+}
+
+fn get_cache_dir() -> Result<path::PathBuf, Box<dyn error::Error>> {
+    let home = env::var("HOME").map_err(|_| ParseError("HOME not set".to_string()))?;
+    let cache_dir = path::PathBuf::from(home).join(".cache").join("pepterm");
+    if !cache_dir.exists() {
+        fs::create_dir_all(&cache_dir)?;
+    }
+    Ok(cache_dir)
+}
+
+pub fn cache_info() -> Result<(usize, u64, path::PathBuf), Box<dyn error::Error>> {
+    let cache_dir = get_cache_dir()?;
+    let mut count = 0;
+    let mut total_size = 0u64;
+
+    if cache_dir.exists() {
+        for entry in fs::read_dir(&cache_dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_file() {
+                count += 1;
+                total_size += metadata.len();
+            }
+        }
+    }
+
+    Ok((count, total_size, cache_dir))
+}
+
+// A cached file's name, size in bytes and last modification time.
+pub type CacheEntry = (String, u64, time::SystemTime);
+
+// Every cached file, largest first.
+pub fn cache_list() -> Result<Vec<CacheEntry>, Box<dyn error::Error>> {
+    let cache_dir = get_cache_dir()?;
+    let mut files = Vec::new();
+
+    for entry in fs::read_dir(&cache_dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_file() {
+            files.push((entry.file_name().to_string_lossy().to_string(), metadata.len(), metadata.modified()?));
+        }
+    }
+
+    files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(files)
+}
+
+// Remove cached files, or with a filter only those whose ID matches it (e.g.
+// `1CRN` or `1C*`), including every chain, quality and representation variant.
+pub fn cache_clear(filter: Option<&str>) -> Result<usize, Box<dyn error::Error>> {
+    let cache_dir = get_cache_dir()?;
+    let mut count = 0;
+
+    if cache_dir.exists() {
+        for entry in fs::read_dir(&cache_dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if filter.is_some_and(|f| !cache_id_matches(f, &name)) {
+                continue;
+            }
+            if entry.metadata()?.is_file() {
+                fs::remove_file(entry.path())?;
+                count += 1;
+            }
+        }
+    }
+
+    Ok(count)
+}
+
+// Cache files start with the entry ID, followed by `_` or the extension.
+fn cache_id_matches(pattern: &str, name: &str) -> bool {
+    let id = name.split(['_', '.']).next().unwrap_or(name);
+    glob_match(pattern.to_uppercase().as_bytes(), id.to_uppercase().as_bytes())
+}
+
+// Shell-style matching where `*` is any run of characters and `?` any one.
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match (pattern.first(), text.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => glob_match(&pattern[1..], text) || (!text.is_empty() && glob_match(pattern, &text[1..])),
+        (Some(b'?'), Some(_)) => glob_match(&pattern[1..], &text[1..]),
+        (Some(p), Some(t)) if p == t => glob_match(&pattern[1..], &text[1..]),
+        _ => false,
+    }
+}
+
+// Delete the least recently modified cache files until the cache fits in
+// `limit` bytes, returning how many were removed.
+pub fn cache_prune(limit: u64) -> Result<usize, Box<dyn error::Error>> {
+    let cache_dir = get_cache_dir()?;
+    let mut files = Vec::new();
+
+    for entry in fs::read_dir(&cache_dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_file() {
+            files.push((metadata.modified()?, metadata.len(), entry.path()));
+        }
+    }
+
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    files.sort_by_key(|(modified, _, _)| *modified);
+
+    let mut removed = 0;
+    for (_, size, path) in files {
+        if total <= limit {
+            break;
+        }
+        fs::remove_file(path)?;
+        total -= size;
+        removed += 1;
+    }
+
+    Ok(removed)
+}
+
+// Oldest PyMOL known to export usable OBJ geometry.
+const MIN_PYMOL_VERSION: (u32, u32) = (2, 0);
+
+// Seconds a network request or PyMOL run may take before it is killed.
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+static TIMEOUT_SECS: sync::atomic::AtomicU64 = sync::atomic::AtomicU64::new(DEFAULT_TIMEOUT_SECS);
+
+pub fn set_timeout(secs: u64) {
+    TIMEOUT_SECS.store(secs, sync::atomic::Ordering::Relaxed);
+}
+
+// How much progress pepterm reports on stderr. Warnings and errors are
+// printed at every level.
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub enum Verbosity {
+    // No progress messages or PyMOL spinner (--quiet).
+    Quiet,
+    Normal,
+    // Also the generated PyMOL scripts (--verbose).
+    Verbose,
+}
+
+static VERBOSITY: sync::atomic::AtomicU8 = sync::atomic::AtomicU8::new(Verbosity::Normal as u8);
+
+pub fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as u8, sync::atomic::Ordering::Relaxed);
+}
+
+pub fn verbosity() -> Verbosity {
+    match VERBOSITY.load(sync::atomic::Ordering::Relaxed) {
+        0 => Verbosity::Quiet,
+        1 => Verbosity::Normal,
+        _ => Verbosity::Verbose,
+    }
+}
+
+// Run a command to completion like `Command::output`, killing it once the
+// `--timeout` has passed. Output is drained on threads so a chatty child
+// can't block on a full pipe.
+fn run_with_timeout(command: &mut Command) -> Result<process::Output, Box<dyn error::Error>> {
+    let secs = TIMEOUT_SECS.load(sync::atomic::Ordering::Relaxed);
+    let mut child = command.stdout(process::Stdio::piped()).stderr(process::Stdio::piped()).spawn()?;
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let start = time::Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if start.elapsed() >= time::Duration::from_secs(secs) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(Box::new(ParseError(format!(
+                "{} did not finish within {} seconds (see --timeout).",
+                command.get_program().to_string_lossy(), secs
+            ))));
+        }
+        thread::sleep(time::Duration::from_millis(20));
+    };
+
+    Ok(process::Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+// Attempts at a network request before giving up, and the pause before the
+// first retry, doubled after each one.
+const NETWORK_ATTEMPTS: u32 = 3;
+const RETRY_DELAY_MS: u64 = 500;
+
+// Run curl, retrying with backoff when the network fails: DNS, connection,
+// TLS and dropped-reply errors, and HTTP 5xx responses under `-f`. A clean
+// answer such as 404 is returned at once for the caller to report.
+fn curl(args: &[&str]) -> Result<process::Output, Box<dyn error::Error>> {
+    curl_attempts(args, NETWORK_ATTEMPTS)
+}
+
+// `curl` giving up after `attempts` tries, e.g. one for optional lookups whose
+// failure is ignored anyway.
+fn curl_attempts(args: &[&str], attempts: u32) -> Result<process::Output, Box<dyn error::Error>> {
+    let mut delay = time::Duration::from_millis(RETRY_DELAY_MS);
+    let mut attempt = 1;
+    loop {
+        // `-S` makes curl name the HTTP status of a `-f` failure on stderr.
+        let output = run_with_timeout(Command::new("curl").arg("-S").args(args))?;
+        let Some(reason) = transient_failure(&output) else {
+            return Ok(output);
+        };
+        if attempt == attempts {
+            let url = args.iter().rev().find(|arg| arg.starts_with("http")).unwrap_or(&"server");
+            return Err(Box::new(ParseError(format!(
+                "Network error: {} for {} after {} attempts. Check your connection and try again.",
+                reason, url, attempt
+            ))));
+        }
+        progress!("Network error: {}; retrying in {:.1}s...", reason, delay.as_secs_f32());
+        thread::sleep(delay);
+        delay *= 2;
+        attempt += 1;
+    }
+}
+
+// Why a curl run failed, if it failed in a way that may pass on a retry.
+fn transient_failure(output: &process::Output) -> Option<String> {
+    let reason = match output.status.code()? {
+        5 | 6 => "could not resolve host",
+        7 => "could not connect",
+        28 => "connection timed out",
+        35 => "TLS handshake failed",
+        52 | 55 | 56 => "connection dropped",
+        22 => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let status = stderr.split("error: ").nth(1)?.get(..3)?.to_string();
+            return status.starts_with('5').then(|| format!("server error {}", status));
+        }
+        _ => return None,
+    };
+    Some(reason.to_string())
+}
+
+// Spinner frames shown while PyMOL works.
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+// PyMOL runs in progress, which may overlap when loading several inputs, and
+// whether a spinner is drawing for them.
+static PYMOL_RUNS: sync::atomic::AtomicUsize = sync::atomic::AtomicUsize::new(0);
+static SPINNER_ACTIVE: sync::atomic::AtomicBool = sync::atomic::AtomicBool::new(false);
+
+// Run a PyMOL script while a spinner with the elapsed time animates on stderr
+// (when it is a terminal), so a long export doesn't look hung.
+fn run_pymol_script(script_path: &path::Path) -> Result<process::Output, Box<dyn error::Error>> {
+    use sync::atomic::Ordering::SeqCst;
+
+    if verbosity() == Verbosity::Verbose {
+        eprintln!("PyMOL script {}:\n{}", script_path.display(), fs::read_to_string(script_path)?.trim());
+    }
+
+    PYMOL_RUNS.fetch_add(1, SeqCst);
+    let quiet = verbosity() == Verbosity::Quiet;
+    if !quiet && io::IsTerminal::is_terminal(&io::stderr()) && SPINNER_ACTIVE.compare_exchange(false, true, SeqCst, SeqCst).is_ok() {
+        thread::spawn(spin_while_pymol_runs);
+    }
+
+    let output = run_with_timeout(Command::new("pymol").args(["-cq", &script_path.to_string_lossy()]));
+
+    // The last run waits for the spinner to clear its line before returning.
+    if PYMOL_RUNS.fetch_sub(1, SeqCst) == 1 {
+        while SPINNER_ACTIVE.load(SeqCst) {
+            thread::sleep(time::Duration::from_millis(10));
+        }
+    }
+    output
+}
+
+fn spin_while_pymol_runs() {
+    let start = time::Instant::now();
+    for frame in SPINNER_FRAMES.iter().cycle() {
+        let running = PYMOL_RUNS.load(sync::atomic::Ordering::SeqCst);
+        if running == 0 {
+            break;
+        }
+        let task = if running > 1 { format!("PyMOL working on {} structures", running) } else { "PyMOL working".to_string() };
+        eprint!("\r\x1b[2K{} {}... {}s", frame, task, start.elapsed().as_secs());
+        thread::sleep(time::Duration::from_millis(100));
+    }
+    eprint!("\r\x1b[2K");
+    SPINNER_ACTIVE.store(false, sync::atomic::Ordering::SeqCst);
+}
+
+// Read a child's pipe to the end on its own thread.
+fn drain<R: io::Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buffer);
+        }
+        buffer
+    })
+}
+
+fn pymol_installed() -> bool {
+    Command::new("which").arg("pymol").output().is_ok_and(|output| output.status.success())
+}
+
+// Make sure PyMOL is installed and return its version string, warning when it
+// is older than known-good.
+fn check_pymol() -> Result<String, Box<dyn error::Error>> {
+    if !pymol_installed() {
+        return Err(Box::new(ParseError(
             "PyMOL not found. Install with: brew install pymol".to_string(),
         )));
     }
+
+    let output = run_with_timeout(Command::new("pymol")
+        .args([
+            "-cq", "-d",
+            "print(cmd.get_version_formatted() if hasattr(cmd, 'get_version_formatted') else cmd.get_version()[0])",
+        ]))?;
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    match parse_pymol_version(&version) {
+        Some(v) if v < MIN_PYMOL_VERSION => eprintln!(
+            "Warning: {} is older than {}.{}; OBJ export may be empty or incomplete.",
+            version, MIN_PYMOL_VERSION.0, MIN_PYMOL_VERSION.1
+        ),
+        Some(_) => {}
+        None => eprintln!("Warning: could not determine the PyMOL version."),
+    }
+
+    if version.is_empty() {
+        Ok("PyMOL (unknown version)".to_string())
+    } else {
+        Ok(version)
+    }
+}
+
+// Parse the major/minor version out of e.g. "PyMOL 2.5.0 Open-Source".
+fn parse_pymol_version(version: &str) -> Option<(u32, u32)> {
+    let number = version.split_whitespace().find(|w| w.starts_with(|c: char| c.is_ascii_digit()))?;
+    let mut parts = number.split('.').map(|p| p.parse::<u32>().ok());
+    Some((parts.next()??, parts.next().flatten().unwrap_or(0)))
+}
+
+// Judge a PyMOL run by the OBJ it was to write: a nonzero exit, which noisy
+// plugins cause even after a good export, only fails the run when the file is
+// missing or empty, and is otherwise reported as a warning.
+fn check_pymol_exit(output: &process::Output, obj_path: &path::Path) -> Result<(), Box<dyn error::Error>> {
+    if output.status.success() {
+        return Ok(());
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !fs::metadata(obj_path).is_ok_and(|metadata| metadata.len() > 0) {
+        return Err(Box::new(ParseError(format!("PyMOL failed: {}", stderr))));
+    }
+    eprintln!("Warning: PyMOL reported failure ({}) but wrote {}; using it. PyMOL said: {}", output.status, obj_path.display(), stderr.trim());
     Ok(())
 }
 
-fn export_cartoon_with_pymol(pdb_input: &str, chain: Option<&str>) -> Result<String, Box<dyn error::Error>> {
-    check_pymol()?;
+// Check PyMOL actually wrote geometry, naming the PyMOL version if not. An
+// empty export of chains without the representation is left for the loader
+// to report in terms of the chain or selection.
+fn verify_obj_output(obj_path: &path::Path, pymol_version: &str, hint: &str) -> Result<(), Box<dyn error::Error>> {
+    let usable = fs::File::open(obj_path).is_ok_and(|file| is_usable_export(io::BufReader::new(file)));
+    if !usable {
+        return Err(Box::new(ParseError(format!(
+            "{} did not create a usable OBJ file.{} Versions older than {}.{} may silently export nothing.",
+            pymol_version, hint, MIN_PYMOL_VERSION.0, MIN_PYMOL_VERSION.1
+        ))));
+    }
+    Ok(())
+}
 
+// Fail early when a PDB ID has been obsoleted, naming the entry that superseded
+// it; PyMOL's fetch would only report that nothing was loaded. Lookup failures
+// are left for the fetch itself to report.
+fn check_obsolete(pdb_id: &str) -> Result<(), Box<dyn error::Error>> {
+    let url = format!("https://data.rcsb.org/rest/v1/holdings/status/{}", pdb_id);
+    // Only a hint, so an unreachable server is not worth waiting out.
+    let Ok(output) = curl_attempts(&["-sf", &url], 1) else {
+        return Ok(());
+    };
+    let response = String::from_utf8_lossy(&output.stdout);
+    if json_string_field(&response, "status").as_deref() != Some("REMOVED") {
+        return Ok(());
+    }
+
+    let replacement = json_string_field(&response, "id_code_replaced_by_latest")
+        .or_else(|| json_array_field(&response, "id_codes_replaced_by").into_iter().next());
+    let message = match replacement {
+        Some(id) => format!("PDB entry {} is obsolete and was superseded by {}. Try: pepterm {}", pdb_id, id, id),
+        None => format!("PDB entry {} is obsolete and has no replacement.", pdb_id),
+    };
+    Err(Box::new(ParseError(message)))
+}
+
+fn export_cartoon_with_pymol(pdb_input: &str, options: &LoadOptions) -> Result<String, Box<dyn error::Error>> {
     let cache_dir = get_cache_dir()?;
     let pdb_id = pdb_input.to_uppercase();
-    let obj_filename = match chain {
-        Some(c) => format!("{}_{}.obj", pdb_id, c.to_uppercase()),
-        None => format!("{}.obj", pdb_id),
-    };
+    let obj_filename = format!("{}.obj", options.cache_stem(&pdb_id));
     let obj_path = cache_dir.join(&obj_filename);
 
-    if obj_path.exists() {
-        eprintln!("Using cached structure from {:?}", obj_path);
+    let center_cached = options.center_path(&obj_path).is_none_or(|p| p.exists());
+    // An asymmetric unit cached in place of the assembly is reused only while
+    // the assembly is still over the limit, saying so each time.
+    let fallback_path = assembly_fallback_path(&obj_path);
+    let fallback_atoms = fs::read_to_string(&fallback_path).ok().and_then(|atoms| atoms.trim().parse::<usize>().ok());
+    let fallback_current = fallback_atoms.is_none_or(|atoms| atoms > MAX_ASSEMBLY_ATOMS);
+    if options.cache_hit(&obj_path) && center_cached && fallback_current {
+        if let Some(atoms) = fallback_atoms {
+            warn_assembly_fallback(&pdb_id, atoms);
+        }
+        progress!("Using cached structure from {:?}", obj_path);
         return Ok(obj_path.to_string_lossy().to_string());
     }
 
-    let selection_cmd = match chain {
-        Some(c) => format!("select sel, chain {}\nhide everything\nshow cartoon, sel", c.to_uppercase()),
-        None => "hide everything\nshow cartoon".to_string(),
+    let pymol_version = check_pymol()?;
+    // Downloaded here rather than by PyMOL's fetch, so failures are retried.
+    let cif_path = fetch_structure(&pdb_id, options)?;
+
+    // Build the assembly while loading, going back to the asymmetric unit
+    // (and saying so on stdout) when it is too big to export in reasonable time.
+    let (assembly_setting, assembly_check) = match options.assembly {
+        Assembly::Asym => (String::new(), String::new()),
+        Assembly::Bio1 => (
+            "set assembly, 1".to_string(),
+            format!(
+                "atoms = cmd.count_atoms(\"all\")\n\
+if atoms > {limit}:\n    print(\"{marker} %d\" % atoms)\n    cmd.delete(\"all\")\n    cmd.set(\"assembly\", \"\")\n    cmd.load(r\"{path}\", \"{id}\")",
+                limit = MAX_ASSEMBLY_ATOMS, marker = ASSEMBLY_FALLBACK_MARKER, path = cif_path, id = pdb_id
+            ),
+        ),
     };
 
     let pymol_script = format!(
         r#"
-set fetch_path, {}
-fetch {}, async=0
 {}
-set cartoon_sampling, 3
-save {}
+load {}, {}
+{}
+python
+{}
+{}
+save_by_chain("{}", "{}", r"{}", {}, {})
+{}
+python end
 quit
 "#,
-        cache_dir.display(), pdb_id, selection_cmd, obj_path.display()
+        assembly_setting, cif_path, pdb_id, options.pymol_settings(),
+        PYMOL_SAVE_BY_CHAIN, assembly_check, options.selection(), options.representation.pymol_name(), obj_path.display(),
+        options.pymol_disulfides(),
+        options.pymol_hbonds(),
+        options.center_commands(&obj_path)?
     );
 
-    let script_path = cache_dir.join("pymol_script.pml");
+    let script_path = obj_path.with_extension("pml");
     fs::write(&script_path, &pymol_script)?;
 
-    eprintln!("Fetching {} and generating cartoon with PyMOL...", pdb_id);
+    // A leftover OBJ would pass for this run's output below.
+    let _ = fs::remove_file(&obj_path);
+    let _ = fs::remove_file(&fallback_path);
+    progress!("Generating cartoon for {} with PyMOL...", pdb_id);
 
-    let output = Command::new("pymol")
-        .args(["-cq", &script_path.to_string_lossy()])
-        .output()?;
+    let output = run_pymol_script(&script_path)?;
+    check_pymol_exit(&output, &obj_path)?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(Box::new(ParseError(format!("PyMOL failed: {}", stderr))));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if let Some(atoms) = stdout.lines().find_map(|line| line.strip_prefix(ASSEMBLY_FALLBACK_MARKER)?.trim().parse().ok()) {
+        warn_assembly_fallback(&pdb_id, atoms);
+        fs::write(&fallback_path, atoms.to_string())?;
     }
 
-    if !obj_path.exists() {
-        return Err(Box::new(ParseError(
-            "PyMOL did not create OBJ file. Check PDB ID.".to_string(),
-        )));
-    }
+    verify_obj_output(&obj_path, &pymol_version, " Check PDB ID.")?;
 
-    eprintln!("Cached to {:?}", obj_path);
+    progress!("Cached to {:?}", obj_path);
     Ok(obj_path.to_string_lossy().to_string())
 }
 
-fn export_cartoon_from_file(file_path: &str, chain: Option<&str>) -> Result<String, Box<dyn error::Error>> {
-    check_pymol()?;
-
-    let cache_dir = get_cache_dir()?;
-    let abs_path = fs::canonicalize(file_path)?;
+// Cache stem for a local file: its name plus a digest of its contents, so
+// edited files regenerate while unchanged ones hit the cache.
+fn local_stem(abs_path: &path::Path) -> Result<String, Box<dyn error::Error>> {
     let file_stem = abs_path.file_stem()
         .and_then(|s| s.to_str())
         .unwrap_or("unknown");
-    let obj_filename = match chain {
-        Some(c) => format!("local_{}_{}.obj", file_stem, c.to_uppercase()),
-        None => format!("local_{}.obj", file_stem),
-    };
+    Ok(format!("local_{}_{:08x}", file_stem, fnv1a(&fs::read(abs_path)?)))
+}
+
+fn export_cartoon_from_file(file_path: &str, options: &LoadOptions) -> Result<String, Box<dyn error::Error>> {
+    let cache_dir = get_cache_dir()?;
+    let abs_path = fs::canonicalize(file_path)?;
+    let obj_filename = format!("{}.obj", options.cache_stem(&local_stem(&abs_path)?));
     let obj_path = cache_dir.join(&obj_filename);
 
-    let selection_cmd = match chain {
-        Some(c) => format!("select sel, chain {}\nhide everything\nshow cartoon, sel", c.to_uppercase()),
-        None => "hide everything\nshow cartoon".to_string(),
-    };
+    let center_cached = options.center_path(&obj_path).is_none_or(|p| p.exists());
+    if obj_path.exists() && center_cached && !options.no_cache {
+        progress!("Using cached structure from {:?}", obj_path);
+        return Ok(obj_path.to_string_lossy().to_string());
+    }
+
+    let pymol_version = check_pymol()?;
 
     let pymol_script = format!(
         r#"
 load {}
 {}
-set cartoon_sampling, 3
-save {}
+python
+{}
+save_by_chain("{}", "{}", r"{}", {}, {})
+{}
+python end
 quit
 "#,
         abs_path.display(),
-        selection_cmd,
-        obj_path.display()
+        options.pymol_settings(),
+        PYMOL_SAVE_BY_CHAIN,
+        options.selection(),
+        options.representation.pymol_name(),
+        obj_path.display(),
+        options.pymol_disulfides(),
+        options.pymol_hbonds(),
+        options.center_commands(&obj_path)?
     );
 
-    let script_path = cache_dir.join("pymol_script.pml");
+    let script_path = obj_path.with_extension("pml");
     fs::write(&script_path, &pymol_script)?;
 
-    eprintln!("Generating cartoon with PyMOL...");
+    // A leftover OBJ would pass for this run's output below.
+    let _ = fs::remove_file(&obj_path);
+    progress!("Generating cartoon with PyMOL...");
+
+    let output = run_pymol_script(&script_path)?;
+    check_pymol_exit(&output, &obj_path)?;
+
+    verify_obj_output(&obj_path, &pymol_version, "")?;
+
+    progress!("Cached to {:?}", obj_path);
+    Ok(obj_path.to_string_lossy().to_string())
+}
+
+// UniProt accession of an `af:<ACCESSION>` input naming an AlphaFold DB model.
+pub fn alphafold_accession(input: &str) -> Option<String> {
+    let (prefix, accession) = input.split_once(':')?;
+    if !prefix.eq_ignore_ascii_case("af") || accession.is_empty() || !accession.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    Some(accession.to_uppercase())
+}
+
+const ALPHAFOLD_MODEL_VERSION: u32 = 4;
+
+// Download an AlphaFold DB prediction into the cache, returning the CIF path.
+fn fetch_alphafold(accession: &str, options: &LoadOptions) -> Result<String, Box<dyn error::Error>> {
+    let filename = format!("AF-{}-F1-model_v{}.cif", accession, ALPHAFOLD_MODEL_VERSION);
+    let cif_path = get_cache_dir()?.join(&filename);
+    if options.cache_hit(&cif_path) {
+        progress!("Using cached prediction from {:?}", cif_path);
+        return Ok(cif_path.to_string_lossy().to_string());
+    }
+
+    progress!("Fetching {} from AlphaFold DB...", accession);
+    let url = format!("https://alphafold.ebi.ac.uk/files/{}", filename);
+    let output = curl(&["-sfL", "-o", &cif_path.to_string_lossy(), &url])?;
+
+    if !output.status.success() {
+        let _ = fs::remove_file(&cif_path);
+        return Err(Box::new(ParseError(format!(
+            "No AlphaFold DB model found for {}. Check the UniProt accession.", accession
+        ))));
+    }
+    Ok(cif_path.to_string_lossy().to_string())
+}
+
+// File formats a URL may point at, by extension.
+const URL_EXTENSIONS: [&str; 6] = ["pdb", "cif", "mol2", "sdf", "xyz", "obj"];
+
+pub fn is_url(input: &str) -> bool {
+    input.starts_with("http://") || input.starts_with("https://")
+}
+
+// The local path for an input: URLs are downloaded into the cache (keyed by
+// a hash of the URL, keeping its extension so the format is recognized);
+// anything else is returned unchanged.
+fn local_input(input: &str, no_cache: bool) -> Result<String, Box<dyn error::Error>> {
+    if !is_url(input) {
+        return Ok(input.to_string());
+    }
+
+    let name = input.split(['?', '#']).next().unwrap_or(input).rsplit('/').next().unwrap_or("");
+    let extension = name.rsplit_once('.').map(|(_, ext)| ext.to_lowercase()).unwrap_or_default();
+    if !URL_EXTENSIONS.contains(&extension.as_str()) {
+        return Err(Box::new(ParseError(format!(
+            "Cannot tell the file format; the URL should end in .{}.", URL_EXTENSIONS.join(", .")
+        ))));
+    }
 
-    let output = Command::new("pymol")
-        .args(["-cq", &script_path.to_string_lossy()])
-        .output()?;
+    let path = get_cache_dir()?.join(format!("url_{:08x}.{}", fnv1a(input.as_bytes()), extension));
+    if path.exists() && !no_cache {
+        return Ok(path.to_string_lossy().to_string());
+    }
 
+    progress!("Downloading {}...", input);
+    let output = curl(&["-sfL", "-o", &path.to_string_lossy(), "-w", "%{content_type}", input])?;
     if !output.status.success() {
+        let _ = fs::remove_file(&path);
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(Box::new(ParseError(format!("PyMOL failed: {}", stderr))));
+        let reason = stderr.split("error: ").nth(1).map_or("download failed", str::trim);
+        return Err(Box::new(ParseError(format!("Could not download: {}", reason))));
+    }
+
+    // Catch error pages and mislabeled files before PyMOL sees them.
+    let content_type = String::from_utf8_lossy(&output.stdout).to_lowercase();
+    let head: String = fs::read_to_string(&path).unwrap_or_default().trim_start().chars().take(5).collect();
+    let found = if content_type.contains("html") || head.starts_with('<') {
+        Some("an HTML page")
+    } else if head == "data_" && extension != "cif" {
+        Some("an mmCIF file")
+    } else if head != "data_" && extension == "cif" {
+        Some("data that is not mmCIF")
+    } else {
+        None
+    };
+    if let Some(found) = found {
+        let _ = fs::remove_file(&path);
+        return Err(Box::new(ParseError(format!(
+            "Expected a .{} file but the server sent {}{}.",
+            extension, found,
+            if content_type.is_empty() { String::new() } else { format!(" ({})", content_type.trim()) }
+        ))));
+    }
+    Ok(path.to_string_lossy().to_string())
+}
+
+// Decide whether to build a native CA trace: `Some` when one was requested,
+// or when PyMOL is missing (switching to a trace and returning a note saying so).
+fn trace_fallback(options: &mut LoadOptions) -> Option<Option<String>> {
+    if matches!(options.representation, Representation::Trace | Representation::Tube) {
+        return Some(None);
+    }
+    if pymol_installed() {
+        return None;
+    }
+    eprintln!("PyMOL not found; drawing a CA trace instead of {}.", options.representation.name());
+    options.representation = Representation::Trace;
+    Some(Some("trace: PyMOL not found".to_string()))
+}
+
+// Whether an input names a structure file rather than a database ID.
+fn is_structure_file(input: &str) -> bool {
+    is_small_molecule(input) || input.ends_with(".pdb") || input.ends_with(".cif")
+        || input.contains('/') || input.contains('\\')
+}
+
+// Small-molecule formats (docked ligands etc.) that have no cartoon.
+pub fn is_small_molecule(input: &str) -> bool {
+    let lower = input.to_lowercase();
+    lower.ends_with(".mol2") || lower.ends_with(".sdf")
+}
+
+// Load a structure, along with the `--center` residue position if requested.
+// OBJ files carry no residue information, so they never have one.
+pub fn new_cartoon(input: &str, options: &LoadOptions, position: three::Point) -> Result<(Model, Option<three::Point>), Box<dyn error::Error>> {
+    let input = &local_input(input, options.no_cache)?;
+    if input.ends_with(".obj") {
+        return Ok((load_obj_colored(input, options.max_edges, position, options.preview.as_ref())?, None));
+    }
+
+    if is_xyz(input) {
+        let first = load_xyz(input, position)?.swap_remove(0);
+        return Ok((first, None));
     }
 
-    if !obj_path.exists() {
+    // Cartoon and ribbon need a protein backbone; show small molecules' bonds instead.
+    let mut options = options.clone();
+    if is_small_molecule(input) && matches!(options.representation, Representation::Cartoon | Representation::Ribbon | Representation::Backbone) {
+        options.representation = Representation::Sticks;
+    }
+
+    if !is_small_molecule(input) {
+        if let Some(note) = trace_fallback(&mut options) {
+            let (mut states, center) = load_trace(input, &options, position)?;
+            let mut model = states.swap_remove(0);
+            model.note = note;
+            return Ok((model, center));
+        }
+    }
+
+    let obj_path = if let Some(accession) = alphafold_accession(input) {
+        export_cartoon_from_file(&fetch_alphafold(&accession, &options)?, &options)?
+    } else if is_structure_file(input) {
+        export_cartoon_from_file(input, &options)?
+    } else {
+        export_cartoon_with_pymol(input, &options)?
+    };
+    let model = options.load_export(path::Path::new(&obj_path), position)?;
+    let center = options.read_center(path::Path::new(&obj_path))?;
+    Ok((model, center))
+}
+
+// Load every state (NMR model / MD snapshot) of a structure as its own model.
+pub fn new_ensemble(input: &str, options: &LoadOptions, position: three::Point) -> Result<Vec<Model>, Box<dyn error::Error>> {
+    let input = &local_input(input, options.no_cache)?;
+    if input.ends_with(".obj") {
         return Err(Box::new(ParseError(
-            "PyMOL did not create OBJ file.".to_string(),
+            "Ensemble mode needs a PDB ID or PDB/CIF file, not an OBJ file.".to_string(),
         )));
     }
 
-    Ok(obj_path.to_string_lossy().to_string())
+    if is_xyz(input) {
+        return load_xyz(input, position);
+    }
+
+    let mut options = options.clone();
+    if !is_small_molecule(input) {
+        if let Some(note) = trace_fallback(&mut options) {
+            let (mut states, _) = load_trace(input, &options, position)?;
+            for state in &mut states {
+                state.note = note.clone();
+            }
+            return Ok(states);
+        }
+    }
+    let options = &options;
+
+    let obj_paths = match alphafold_accession(input) {
+        Some(accession) => export_ensemble_with_pymol(&fetch_alphafold(&accession, options)?, options)?,
+        None => export_ensemble_with_pymol(input, options)?,
+    };
+    obj_paths.iter()
+        .map(|path| options.load_export(path, position))
+        .collect()
 }
 
-pub fn new_cartoon(input: &str, chain: Option<&str>, position: three::Point) -> Result<Model, Box<dyn error::Error>> {
-    if input.ends_with(".obj") {
-        return load_obj_colored(input, position);
+fn export_ensemble_with_pymol(input: &str, options: &LoadOptions) -> Result<Vec<path::PathBuf>, Box<dyn error::Error>> {
+    let cache_dir = get_cache_dir()?;
+    let is_file = is_structure_file(input);
+
+    let (abs_path, stem) = if is_file {
+        let abs_path = fs::canonicalize(input)?;
+        let stem = local_stem(&abs_path)?;
+        (Some(abs_path), stem)
+    } else {
+        (None, input.to_uppercase())
+    };
+    let prefix = format!("{}_state", options.cache_stem(&stem));
+
+    let cached = list_state_files(&cache_dir, &prefix)?;
+    if options.no_cache || (!is_file && cached.iter().any(|path| options.expired(path))) {
+        // Drop stale states so a smaller ensemble doesn't inherit leftovers.
+        for path in &cached {
+            fs::remove_file(path)?;
+        }
+    } else if !cached.is_empty() {
+        progress!("Using {} cached states from {:?}", cached.len(), cache_dir);
+        return Ok(cached);
+    }
+
+    let pymol_version = check_pymol()?;
+    let load_cmd = match abs_path {
+        Some(abs_path) => format!("load {}", abs_path.display()),
+        None => format!("load {}, {}", fetch_structure(&stem, options)?, stem),
+    };
+
+    // Split each state into its own object and save them one at a time. The
+    // selection is its own string, as it may hold `%` (e.g. `%obj`).
+    let pymol_script = format!(
+        r#"
+{load_cmd}
+{settings}
+python
+{save_by_chain}
+source = cmd.get_object_list()[0]
+cmd.split_states(source, prefix="state_")
+cmd.delete(source)
+for i, name in enumerate(sorted(cmd.get_object_list()), 1):
+    save_by_chain("%s and (%s)" % (name, "{selection}"), "{repr}", r"{dir}/{prefix}%04d.obj" % i, {disulfides}, {hbonds})
+python end
+quit
+"#,
+        load_cmd = load_cmd,
+        settings = options.pymol_settings(),
+        save_by_chain = PYMOL_SAVE_BY_CHAIN,
+        repr = options.representation.pymol_name(),
+        selection = options.selection(),
+        disulfides = options.pymol_disulfides(),
+        hbonds = options.pymol_hbonds(),
+        dir = cache_dir.display(),
+        prefix = prefix,
+    );
+
+    let script_path = cache_dir.join(format!("{}.pml", prefix));
+    fs::write(&script_path, &pymol_script)?;
+
+    progress!("Generating ensemble states with PyMOL...");
+
+    let output = run_pymol_script(&script_path)?;
+
+    // States are saved in order, so the last one written stands for the run;
+    // with none written there is nothing to fall back on.
+    let states = list_state_files(&cache_dir, &prefix)?;
+    check_pymol_exit(&output, &states.last().cloned().unwrap_or_default())?;
+    if states.is_empty() {
+        return Err(Box::new(ParseError(
+            format!("{} did not create any state OBJ files.", pymol_version),
+        )));
+    }
+
+    progress!("Cached {} states to {:?}", states.len(), cache_dir);
+    Ok(states)
+}
+
+// Load several structures with each later one superposed onto the first by
+// PyMOL's `align`, so they share a coordinate frame. The RMSD of every
+// alignment is reported on stderr, from the cache too.
+pub fn new_aligned(inputs: &[String], options: &LoadOptions, position: three::Point) -> Result<Vec<Model>, Box<dyn error::Error>> {
+    if matches!(options.representation, Representation::Trace | Representation::Tube) {
+        return Err(Box::new(ParseError("--align runs PyMOL, so it cannot use the trace or tube representation.".to_string())));
+    }
+    let cache_dir = get_cache_dir()?;
+
+    // PyMOL command loading each input as its own object, and the input's cache stem.
+    let mut loads = Vec::new();
+    let mut stems = Vec::new();
+    let mut fetched = Vec::new();
+    for (i, input) in inputs.iter().enumerate() {
+        let input = &local_input(input, options.no_cache)?;
+        if input.ends_with(".obj") || is_xyz(input) {
+            return Err(Box::new(ParseError(format!("--align needs PDB IDs or PDB/CIF files; {} has no residues to align.", input))));
+        }
+        let file = match alphafold_accession(input) {
+            Some(accession) => Some(fetch_alphafold(&accession, options)?),
+            None if is_structure_file(input) => Some(input.clone()),
+            None => None,
+        };
+        match file {
+            Some(file) => {
+                let abs_path = fs::canonicalize(&file)?;
+                loads.push(format!("load {}, input_{}", abs_path.display(), i));
+                stems.push(local_stem(&abs_path)?);
+            }
+            None => {
+                let pdb_id = input.to_uppercase();
+                stems.push(pdb_id.clone());
+                fetched.push((i, pdb_id));
+            }
+        }
+    }
+
+    // Each structure gets its own OBJ, named after its stem.
+    if let Some(i) = (1..stems.len()).find(|&i| stems[..i].contains(&stems[i])) {
+        return Err(Box::new(ParseError(format!("--align got {} twice; give each structure once.", inputs[i]))));
+    }
+
+    // The frame depends on the whole set, so every output carries its digest.
+    let digest = fnv1a(stems.join(" ").as_bytes());
+    let obj_paths: Vec<path::PathBuf> = stems.iter()
+        .map(|stem| cache_dir.join(format!("{}_aligned{:08x}.obj", options.cache_stem(stem), digest)))
+        .collect();
+    let rmsd_path = obj_paths[0].with_extension("rmsd");
+    let pairs_path = obj_paths[0].with_extension("pairs");
+
+    let cached = options.cache_hit(&rmsd_path) && options.cache_hit(&pairs_path) && obj_paths.iter().all(|p| options.cache_hit(p));
+    if cached {
+        progress!("Using cached alignment from {:?}", cache_dir);
+    } else {
+        let pymol_version = check_pymol()?;
+        for (i, pdb_id) in &fetched {
+            loads.push(format!("load {}, input_{}", fetch_structure(pdb_id, options)?, i));
+        }
+
+        let paths: Vec<String> = obj_paths.iter().map(|p| format!("r\"{}\"", p.display())).collect();
+        let pymol_script = format!(
+            r#"
+{loads}
+{settings}
+python
+{save_by_chain}
+names = ["input_%d" % i for i in range({count})]
+selection = "{selection}"
+residues = {{}}
+for name in names:
+    cmd.iterate(name, "residues[(model, index)] = (chain or '-', resi)", space={{"residues": residues}})
+with open(r"{rmsd_path}", "w") as out, open(r"{pairs_path}", "w") as pairs:
+    for i, name in enumerate(names[1:], 1):
+        result = cmd.align("%s and (%s)" % (name, selection), "%s and (%s)" % (names[0], selection), object="aln")
+        out.write("%f %d\n" % (result[0], result[1]))
+        seen = set()
+        for a, b in cmd.get_raw_alignment("aln"):
+            pair = residues[a] + residues[b] if a[0] == name else residues[b] + residues[a]
+            if pair not in seen:
+                seen.add(pair)
+                pairs.write("%d %s %s %s %s\n" % ((i,) + pair))
+        cmd.delete("aln")
+for name, path in zip(names, [{paths}]):
+    save_by_chain("%s and (%s)" % (name, selection), "{repr}", path, {disulfides}, {hbonds})
+python end
+quit
+"#,
+            loads = loads.join("\n"),
+            settings = options.pymol_settings(),
+            save_by_chain = PYMOL_SAVE_BY_CHAIN,
+            count = inputs.len(),
+            rmsd_path = rmsd_path.display(),
+            pairs_path = pairs_path.display(),
+            selection = options.selection(),
+            paths = paths.join(", "),
+            repr = options.representation.pymol_name(),
+            disulfides = options.pymol_disulfides(),
+            hbonds = options.pymol_hbonds(),
+        );
+
+        let script_path = rmsd_path.with_extension("pml");
+        fs::write(&script_path, &pymol_script)?;
+
+        // Leftover OBJs would pass for this run's output below.
+        for obj_path in &obj_paths {
+            let _ = fs::remove_file(obj_path);
+        }
+        progress!("Aligning {} onto {} with PyMOL...", inputs[1..].join(", "), inputs[0]);
+
+        let output = run_pymol_script(&script_path)?;
+
+        // The OBJs are saved in order, so the last one stands for the run.
+        check_pymol_exit(&output, &obj_paths[obj_paths.len() - 1])?;
+        for obj_path in &obj_paths {
+            verify_obj_output(obj_path, &pymol_version, "")?;
+        }
+        progress!("Cached to {:?}", cache_dir);
+    }
+
+    // One "rmsd atoms" line per aligned structure.
+    let rmsd = fs::read_to_string(&rmsd_path)?;
+    for (input, line) in inputs[1..].iter().zip(rmsd.lines()) {
+        let mut fields = line.split_whitespace();
+        if let (Some(Ok(value)), Some(atoms)) = (fields.next().map(str::parse::<f32>), fields.next()) {
+            eprintln!("Aligned {} onto {}: RMSD {:.2} Å over {} atoms", input, inputs[0], value, atoms);
+        }
+    }
+
+    let mut models = obj_paths.iter()
+        .map(|path| options.load_export(path, position))
+        .collect::<Result<Vec<Model>, _>>()?;
+    share_reference_ramp(&mut models, &read_alignment_pairs(&pairs_path)?);
+    Ok(models)
+}
+
+// A residue as `chain resi` in an alignment pairs file; `-` is a blank chain.
+type ResidueKey = (Option<String>, i32);
+// The index of an aligned structure, one of its residues, and the reference
+// residue PyMOL paired it with.
+type ResiduePair = (usize, ResidueKey, ResidueKey);
+
+// One "structure chain resi ref_chain ref_resi" line per aligned residue pair,
+// as the alignment script writes them.
+fn read_alignment_pairs(path: &path::Path) -> Result<Vec<ResiduePair>, Box<dyn error::Error>> {
+    let residue = |chain: &str, resi: &str| {
+        // Insertion codes (e.g. `52A`) share their residue number.
+        let number: String = resi.chars().take_while(|c| *c == '-' || c.is_ascii_digit()).collect();
+        let chain = (chain != "-").then(|| chain.to_string());
+        number.parse().ok().map(|resi| (chain, resi))
+    };
+    let mut pairs = Vec::new();
+    for line in fs::read_to_string(path)?.lines() {
+        if let [index, chain, resi, ref_chain, ref_resi] = line.split_whitespace().collect::<Vec<_>>()[..] {
+            if let (Ok(index), Some(mobile), Some(target)) = (index.parse(), residue(chain, resi), residue(ref_chain, ref_resi)) {
+                pairs.push((index, mobile, target));
+            }
+        }
     }
+    Ok(pairs)
+}
+
+// Give residues of aligned structures the ramp position of the reference
+// residue they were paired with, so a color scheme paints equivalent residues
+// alike. Unpaired residues (insertions, unaligned termini) take the position
+// of the nearest paired residue in their chain.
+fn share_reference_ramp(models: &mut [Model], pairs: &[ResiduePair]) {
+    let Some((reference, aligned)) = models.split_first_mut() else { return };
+
+    // Average ramp position of each reference residue over its edge ends.
+    let mut reference_t: collections::HashMap<ResidueKey, (f32, usize)> = collections::HashMap::new();
+    for edge in &reference.colored_edges {
+        for (resi, t) in [(edge.start_resi, edge.start_t), (edge.end_resi, edge.end_t)] {
+            if let Some(resi) = resi {
+                let entry = reference_t.entry((edge.chain_id.clone(), resi)).or_insert((0., 0));
+                entry.0 += t;
+                entry.1 += 1;
+            }
+        }
+    }
+
+    for (index, model) in aligned.iter_mut().enumerate() {
+        let mut chains: collections::HashMap<Option<String>, collections::BTreeMap<i32, f32>> = collections::HashMap::new();
+        for (_, (chain, resi), target) in pairs.iter().filter(|pair| pair.0 == index + 1) {
+            if let Some((sum, count)) = reference_t.get(target) {
+                chains.entry(chain.clone()).or_default().insert(*resi, sum / *count as f32);
+            }
+        }
 
-    if input.ends_with(".pdb") || input.ends_with(".cif") || input.contains('/') || input.contains('\\') {
-        let obj_path = export_cartoon_from_file(input, chain)?;
-        return load_obj_colored(&obj_path, position);
+        let shared_t = |chain: &Option<String>, resi: Option<i32>| {
+            let (ramp, resi) = (chains.get(chain)?, resi?);
+            let below = ramp.range(..=resi).next_back();
+            let above = ramp.range(resi..).next();
+            match (below, above) {
+                (Some(b), Some(a)) => Some(if resi - b.0 <= a.0 - resi { *b.1 } else { *a.1 }),
+                (Some((_, t)), None) | (None, Some((_, t))) => Some(*t),
+                (None, None) => None,
+            }
+        };
+        for edge in &mut model.colored_edges {
+            if let Some(t) = shared_t(&edge.chain_id, edge.start_resi) {
+                edge.start_t = t;
+            }
+            if let Some(t) = shared_t(&edge.chain_id, edge.end_resi) {
+                edge.end_t = t;
+            }
+        }
     }
+}
 
-    let obj_path = export_cartoon_with_pymol(input, chain)?;
-    load_obj_colored(&obj_path, position)
+fn list_state_files(cache_dir: &path::Path, prefix: &str) -> Result<Vec<path::PathBuf>, Box<dyn error::Error>> {
+    let mut states = Vec::new();
+    for entry in fs::read_dir(cache_dir)? {
+        let path = entry?.path();
+        let is_state = path.file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with(prefix) && n.ends_with(".obj"));
+        if is_state {
+            states.push(path);
+        }
+    }
+    states.sort();
+    Ok(states)
 }
 
-pub fn search_pdb(query: &str) -> Result<Vec<PdbSearchResult>, Box<dyn error::Error>> {
+// Returns the requested page of results along with the total match count.
+pub fn search_pdb(query: &str, start: usize, rows: usize) -> Result<(Vec<PdbSearchResult>, usize), Box<dyn error::Error>> {
     let search_url = "https://search.rcsb.org/rcsbsearch/v2/query";
 
     let search_json = format!(r#"{{
@@ -430,29 +2599,37 @@ pub fn search_pdb(query: &str) -> Result<Vec<PdbSearchResult>, Box<dyn error::Er
         "return_type": "entry",
         "request_options": {{
             "paginate": {{
-                "start": 0,
-                "rows": 10
+                "start": {},
+                "rows": {}
             }},
             "results_content_type": ["experimental"]
         }}
-    }}"#, query);
+    }}"#, query, start, rows);
 
-    let output = Command::new("curl")
-        .args([
-            "-s",
-            "-X", "POST",
-            "-H", "Content-Type: application/json",
-            "-d", &search_json,
-            search_url
-        ])
-        .output()?;
+    let output = curl(&[
+        "-sf",
+        "-X", "POST",
+        "-H", "Content-Type: application/json",
+        "-d", &search_json,
+        search_url
+    ])?;
 
     if !output.status.success() {
         return Err(Box::new(ParseError("Search request failed".to_string())));
     }
 
     let response = String::from_utf8_lossy(&output.stdout);
-    parse_search_results(&response)
+    let results = parse_search_results(&response)?;
+    let total = parse_total_count(&response).unwrap_or(results.len());
+    Ok((results, total))
+}
+
+fn parse_total_count(json: &str) -> Option<usize> {
+    let key_start = json.find("\"total_count\"")?;
+    let rest = &json[key_start + "\"total_count\"".len()..];
+    let after_colon = rest[rest.find(':')? + 1..].trim_start();
+    let digits: String = after_colon.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
 }
 
 #[derive(Debug, Clone)]
@@ -491,8 +2668,16 @@ fn parse_search_results(json: &str) -> Result<Vec<PdbSearchResult>, Box<dyn erro
         }
     }
 
-    for result in &mut results {
-        if let Ok(title) = fetch_pdb_title(&result.pdb_id) {
+    // Fetch titles concurrently, one thread per result, joining in order.
+    let handles: Vec<_> = results.iter()
+        .map(|result| {
+            let pdb_id = result.pdb_id.clone();
+            thread::spawn(move || fetch_pdb_title(&pdb_id).ok())
+        })
+        .collect();
+
+    for (result, handle) in results.iter_mut().zip(handles) {
+        if let Ok(Some(title)) = handle.join() {
             result.title = title;
         }
     }
@@ -503,9 +2688,7 @@ fn parse_search_results(json: &str) -> Result<Vec<PdbSearchResult>, Box<dyn erro
 fn fetch_pdb_title(pdb_id: &str) -> Result<String, Box<dyn error::Error>> {
     let url = format!("https://data.rcsb.org/rest/v1/core/entry/{}", pdb_id);
 
-    let output = Command::new("curl")
-        .args(["-s", &url])
-        .output()?;
+    let output = curl(&["-sf", &url])?;
 
     let response = String::from_utf8_lossy(&output.stdout);
 
@@ -519,30 +2702,315 @@ fn fetch_pdb_title(pdb_id: &str) -> Result<String, Box<dyn error::Error>> {
     Ok(String::new())
 }
 
-#[allow(dead_code)]
-pub fn get_pdb_chains(pdb_id: &str) -> Result<Vec<String>, Box<dyn error::Error>> {
+fn fetch_entry_json(pdb_id: &str) -> Result<String, Box<dyn error::Error>> {
     let url = format!("https://data.rcsb.org/rest/v1/core/entry/{}", pdb_id);
 
-    let output = Command::new("curl")
-        .args(["-s", &url])
-        .output()?;
+    let output = curl(&["-sf", &url])?;
+
+    // Server errors were retried by `curl`; what is left means no such entry.
+    if !output.status.success() {
+        return Err(Box::new(ParseError(format!("No entry found for {}", pdb_id))));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn fetch_polymer_entity_json(pdb_id: &str, entity_id: &str) -> Result<String, Box<dyn error::Error>> {
+    let url = format!("https://data.rcsb.org/rest/v1/core/polymer_entity/{}/{}", pdb_id, entity_id);
+
+    let output = curl(&["-sf", &url])?;
+
+    if !output.status.success() {
+        return Err(Box::new(ParseError(format!("Polymer entity {} request failed", entity_id))));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+pub fn get_pdb_chains(pdb_id: &str) -> Result<Vec<String>, Box<dyn error::Error>> {
+    let response = fetch_entry_json(pdb_id)?;
+    fetch_entry_chains(pdb_id, &response)
+}
+
+// Author chain IDs of an entry. The entry itself only lists its polymer
+// entities, so each entity is fetched (concurrently, joining in order) for
+// the chains holding a copy of it.
+fn fetch_entry_chains(pdb_id: &str, entry: &str) -> Result<Vec<String>, Box<dyn error::Error>> {
+    let handles: Vec<_> = parse_polymer_entity_ids(entry).into_iter()
+        .map(|entity_id| {
+            let pdb_id = pdb_id.to_string();
+            thread::spawn(move || fetch_polymer_entity_json(&pdb_id, &entity_id).map_err(|e| e.to_string()))
+        })
+        .collect();
 
-    let response = String::from_utf8_lossy(&output.stdout);
     let mut chains = Vec::new();
-    let mut pos = 0;
-    while let Some(chain_start) = response[pos..].find("\"auth_asym_id\"") {
-        let id_pos = pos + chain_start + 16;
-        if let Some(quote_end) = response[id_pos..].find('"') {
-            let chain = response[id_pos..id_pos + quote_end].to_string();
-            if !chains.contains(&chain) {
-                chains.push(chain);
-            }
-            pos = id_pos + quote_end;
-        } else {
-            break;
-        }
+    for handle in handles {
+        let entity = handle.join()
+            .map_err(|_| "Polymer entity request panicked".to_string())
+            .and_then(|entity| entity)
+            .map_err(ParseError)?;
+        chains.extend(parse_entity_chains(&entity));
     }
 
     chains.sort();
+    chains.dedup();
     Ok(chains)
 }
+
+fn parse_polymer_entity_ids(entry: &str) -> Vec<String> {
+    json_object_field(entry, "rcsb_entry_container_identifiers")
+        .map(|ids| json_array_field(ids, "polymer_entity_ids"))
+        .unwrap_or_default()
+}
+
+fn parse_entity_chains(entity: &str) -> Vec<String> {
+    json_object_field(entity, "rcsb_polymer_entity_container_identifiers")
+        .map(|ids| json_array_field(ids, "auth_asym_ids"))
+        .unwrap_or_default()
+}
+
+// Chain IDs of a structure: read from the file for local PDB/CIF inputs, from
+// RCSB for PDB IDs. Inputs without chains (OBJ, XYZ, small molecules and
+// single-chain AlphaFold models) give none.
+pub fn list_chains(input: &str) -> Result<Vec<String>, Box<dyn error::Error>> {
+    let input = &local_input(input, false)?;
+    if input.ends_with(".obj") || is_xyz(input) || is_small_molecule(input) || alphafold_accession(input).is_some() {
+        return Ok(Vec::new());
+    }
+    if !is_structure_file(input) {
+        return get_pdb_chains(&input.to_uppercase());
+    }
+
+    let text = fs::read_to_string(input)?;
+    let chains = if input.to_lowercase().ends_with(".cif") {
+        parse_cif_chains(&text)
+    } else {
+        parse_pdb_chains(&text)
+    };
+    if chains.is_empty() {
+        return Err(Box::new(ParseError(format!("No ATOM or HETATM records found in {}", input))));
+    }
+    Ok(chains)
+}
+
+// Chain IDs of every `ATOM` and `HETATM` record in file order, so nucleic
+// acid and ligand-only chains are listed along with protein ones.
+fn parse_pdb_chains(text: &str) -> Vec<String> {
+    let mut chains: Vec<String> = Vec::new();
+    for line in text.lines() {
+        if !(line.starts_with("ATOM") || line.starts_with("HETATM")) {
+            continue;
+        }
+        let chain = line.get(21..22).map(str::trim).unwrap_or("");
+        if !chains.iter().any(|c| c == chain) {
+            chains.push(chain.to_string());
+        }
+    }
+    chains
+}
+
+// Chain IDs of the `ATOM` and `HETATM` rows of the mmCIF `_atom_site` loop.
+fn parse_cif_chains(text: &str) -> Vec<String> {
+    let mut columns: Vec<&str> = Vec::new();
+    let mut chains: Vec<String> = Vec::new();
+    let mut lines = text.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        if line.trim() != "loop_" || !lines.peek().is_some_and(|l| l.starts_with("_atom_site.")) {
+            continue;
+        }
+        while let Some(header) = lines.next_if(|l| l.starts_with("_atom_site.")) {
+            columns.push(header.trim().trim_start_matches("_atom_site."));
+        }
+        let column = |names: &[&str]| names.iter().find_map(|name| columns.iter().position(|c| c == name));
+        let (Some(group), Some(chain)) = (column(&["group_PDB"]), column(&["auth_asym_id", "label_asym_id"])) else { break };
+
+        while let Some(row) = lines.next_if(|l| !l.starts_with('#') && !l.starts_with('_') && l.trim() != "loop_") {
+            let fields = cif_tokens(row);
+            if fields.len() < columns.len() || !matches!(fields[group], "ATOM" | "HETATM") {
+                continue;
+            }
+            if !chains.iter().any(|c| c == fields[chain]) {
+                chains.push(fields[chain].to_string());
+            }
+        }
+        break;
+    }
+    chains
+}
+
+// Summary of an RCSB entry, as shown by `pepterm info`.
+pub struct PdbMetadata {
+    pub pdb_id: String,
+    pub title: String,
+    pub method: Option<String>,
+    pub resolution: Option<f32>,
+    pub chains: Vec<String>,
+    pub ligands: Vec<String>,
+}
+
+pub fn fetch_pdb_metadata(pdb_id: &str) -> Result<PdbMetadata, Box<dyn error::Error>> {
+    let pdb_id = pdb_id.to_uppercase();
+    let response = fetch_entry_json(&pdb_id)?;
+
+    if !response.contains("\"rcsb_id\"") {
+        return Err(Box::new(ParseError(format!("No entry found for {}", pdb_id))));
+    }
+
+    // The first `title` is usually a citation's, so read the entry's own.
+    let title = json_object_field(&response, "struct")
+        .and_then(|object| json_string_field(object, "title"))
+        .unwrap_or_default();
+    let method = json_string_field(&response, "method");
+    let resolution = json_array_field(&response, "resolution_combined")
+        .first()
+        .and_then(|r| r.parse::<f32>().ok());
+    let chains = fetch_entry_chains(&pdb_id, &response)?;
+    let ligands = json_array_field(&response, "nonpolymer_bound_components");
+
+    Ok(PdbMetadata { pdb_id, title, method, resolution, chains, ligands })
+}
+
+// Find the first `"key": "value"` pair and return the value.
+fn json_string_field(json: &str, key: &str) -> Option<String> {
+    let key_pattern = format!("\"{}\"", key);
+    let rest = &json[json.find(&key_pattern)? + key_pattern.len()..];
+    let after_colon = rest[rest.find(':')? + 1..].trim_start();
+    let value = after_colon.strip_prefix('"')?;
+
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(result),
+            '\\' => result.extend(chars.next()),
+            _ => result.push(c),
+        }
+    }
+    None
+}
+
+// Find the first `"key": {...}` pair and return the object, braces included.
+fn json_object_field<'a>(json: &'a str, key: &str) -> Option<&'a str> {
+    let key_pattern = format!("\"{}\"", key);
+    let rest = &json[json.find(&key_pattern)? + key_pattern.len()..];
+    let object = rest[rest.find(':')? + 1..].trim_start();
+    if !object.starts_with('{') {
+        return None;
+    }
+
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in object.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&object[..=i]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// Find the first `"key": [...]` pair and return its elements with quotes stripped.
+fn json_array_field(json: &str, key: &str) -> Vec<String> {
+    let key_pattern = format!("\"{}\"", key);
+    let Some(key_start) = json.find(&key_pattern) else {
+        return Vec::new();
+    };
+    let rest = &json[key_start + key_pattern.len()..];
+    let (Some(open), Some(close)) = (rest.find('['), rest.find(']')) else {
+        return Vec::new();
+    };
+    if close < open {
+        return Vec::new();
+    }
+
+    rest[open + 1..close]
+        .split(',')
+        .map(|item| item.trim().trim_matches('"').to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn obj_lines_joins_continuations() {
+        let lines: Vec<String> = obj_lines("v 1 2 \\\n3\nf 1 \\\n2 \\\n3\n".as_bytes())
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(lines, ["v 1 2  3", "f 1  2  3", ""]);
+    }
+
+    #[test]
+    fn is_usable_export_stops_at_geometry() {
+        assert!(is_usable_export("g chain_A\n#ca 1 0 0 0\nv 0 0 0\n".as_bytes()));
+        assert!(is_usable_export("g chain_A\n#ca 1 0 0 0\n".as_bytes()));
+        assert!(!is_usable_export("".as_bytes()));
+        assert!(!is_usable_export("# nothing here\n".as_bytes()));
+    }
+
+    // Entry and polymer entity responses for 4HHB in the shape RCSB returns, cut down.
+    const ENTRY_JSON: &str = r#"{"audit_author":[{"name":"Fermi, G.","pdbx_ordinal":1}],
+"citation":[{"id":"primary","title":"The crystal structure of human deoxyhaemoglobin at 1.74 A resolution","year":1984}],
+"exptl":[{"method":"X-RAY DIFFRACTION"}],
+"rcsb_entry_container_identifiers":{"assembly_ids":["1"],"entity_ids":["1","2","3","4","5"],"entry_id":"4HHB","non_polymer_entity_ids":["3","4","5"],"polymer_entity_ids":["1","2"],"rcsb_id":"4HHB"},
+"rcsb_entry_info":{"nonpolymer_bound_components":["HEM"],"resolution_combined":[1.74]},
+"rcsb_id":"4HHB",
+"struct":{"pdbx_descriptor":"HEMOGLOBIN (DEOXY) (HUMAN)","title":"THE CRYSTAL STRUCTURE OF HUMAN DEOXYHAEMOGLOBIN AT 1.74 \"A\" RESOLUTION"},
+"struct_keywords":{"pdbx_keywords":"OXYGEN TRANSPORT","text":"OXYGEN TRANSPORT"}}"#;
+    const ENTITY_JSON: &str = r#"{"entity_poly":{"pdbx_strand_id":"B,D","type":"polypeptide(L)"},
+"rcsb_polymer_entity_container_identifiers":{"asym_ids":["B","D"],"auth_asym_ids":["B","D"],"entity_id":"2","entry_id":"4HHB","rcsb_id":"4HHB_2"},
+"rcsb_polymer_entity":{"pdbx_description":"Hemoglobin subunit beta"}}"#;
+
+    #[test]
+    fn entry_title_comes_from_struct() {
+        let title = json_object_field(ENTRY_JSON, "struct").and_then(|object| json_string_field(object, "title"));
+        assert_eq!(title.as_deref(), Some("THE CRYSTAL STRUCTURE OF HUMAN DEOXYHAEMOGLOBIN AT 1.74 \"A\" RESOLUTION"));
+    }
+
+    #[test]
+    fn entry_chains_come_from_polymer_entities() {
+        assert_eq!(parse_polymer_entity_ids(ENTRY_JSON), ["1", "2"]);
+        assert_eq!(parse_entity_chains(ENTITY_JSON), ["B", "D"]);
+    }
+
+    #[test]
+    fn chains_include_nucleic_acid_and_ligand_records() {
+        let pdb = "\
+ATOM      1  CA  ALA A   1      11.104   6.134  -6.504  1.00  0.00           C
+ATOM      2  P    DA B   1       1.000   2.000   3.000  1.00  0.00           P
+HETATM    3 FE   HEM C   1       4.000   5.000   6.000  1.00  0.00          FE
+";
+        assert_eq!(parse_pdb_chains(pdb), ["A", "B", "C"]);
+        let cif = "\
+loop_
+_atom_site.group_PDB
+_atom_site.label_atom_id
+_atom_site.auth_asym_id
+ATOM CA A
+ATOM P B
+HETATM FE C
+#
+";
+        assert_eq!(parse_cif_chains(cif), ["A", "B", "C"]);
+    }
+}
@@ -0,0 +1,46 @@
+use std::*;
+use std::fmt::Write;
+
+use crate::model::Model;
+use crate::three::Point;
+use crate::screen::Rgb;
+
+// Write the edges pepterm draws as an OBJ line mesh, one object per model.
+// Vertices carry their edge color as trailing `r g b` (0–1), the extension
+// read by MeshLab and most viewers; endpoints sharing a position and color
+// become one vertex so chains stay connected.
+pub fn to_obj(models: &[Model]) -> String {
+    let mut vertices = String::new();
+    let mut lines = String::new();
+    let mut indices = collections::HashMap::<(u32, u32, u32, u8, u8, u8), usize>::new();
+
+    for (i, model) in models.iter().enumerate() {
+        let _ = writeln!(lines, "o model{}", i + 1);
+        let mut vertex = |point: &Point, color: Rgb| {
+            let point = model.model_to_world(point);
+            let count = indices.len();
+            *indices.entry((point.x.to_bits(), point.y.to_bits(), point.z.to_bits(), color.r, color.g, color.b)).or_insert_with(|| {
+                let _ = writeln!(
+                    vertices,
+                    "v {:.3} {:.3} {:.3} {:.4} {:.4} {:.4}",
+                    point.x, point.y, point.z,
+                    color.r as f32 / 255., color.g as f32 / 255., color.b as f32 / 255.
+                );
+                count + 1
+            })
+        };
+
+        for edge in &model.colored_edges {
+            let start = vertex(&edge.start, edge.start_color);
+            let end = vertex(&edge.end, edge.end_color);
+            let _ = writeln!(lines, "l {} {}", start, end);
+        }
+        for (start, end) in &model.edges {
+            let start = vertex(start, Rgb::white());
+            let end = vertex(end, Rgb::white());
+            let _ = writeln!(lines, "l {} {}", start, end);
+        }
+    }
+
+    format!("# pepterm line geometry\n{}{}", vertices, lines)
+}
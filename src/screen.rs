@@ -3,6 +3,8 @@
 
 use std::*;
 use std::io::Write;
+use rayon::prelude::*;
+use unicode_width::UnicodeWidthChar;
 use crossterm::{
     execute,
     terminal,
@@ -11,8 +13,106 @@ use crossterm::{
 
 const DEFAULT_TERMINAL_DIMENSIONS: (u16, u16) = (80, 24);
 
+// Smallest terminal (in characters, excluding the status row) we try to draw into.
+const MIN_USABLE_DIMENSIONS: (u16, u16) = (10, 3);
+
+// Antialiased pixels below this coverage are left off to keep lines thin.
+const AA_MIN_COVERAGE: f32 = 0.2;
+
+// Pixels in each dash of a dashed line, and in each gap between dashes.
+const DASH_PIXELS: i32 = 2;
+
+// Viewport labels in their bottom row.
+const LABEL_COLOR: Rgb = Rgb { r: 200, g: 200, b: 200 };
+// Residue annotations, brighter so they stand out over the lines.
+const ANNOTATION_COLOR: Rgb = Rgb { r: 255, g: 255, b: 255 };
+
+// A piece of status-bar text, optionally drawn in a color.
+pub type Span = (String, Option<Rgb>);
+
+// How colors are written to the terminal.
+#[derive(Copy, Clone, PartialEq)]
+pub enum ColorDepth {
+    TrueColor,
+    // Nearest entry of the xterm 256-color palette, for terminals (and logs)
+    // that don't understand 24-bit escapes.
+    Xterm256,
+}
+
+impl ColorDepth {
+    pub fn from_str(s: &str) -> Option<ColorDepth> {
+        match s.to_lowercase().as_str() {
+            "truecolor" | "24bit" => Some(ColorDepth::TrueColor),
+            "256" => Some(ColorDepth::Xterm256),
+            _ => None,
+        }
+    }
+
+    // Truecolor when $COLORTERM advertises it, as most such terminals do.
+    pub fn detect() -> ColorDepth {
+        match env::var("COLORTERM") {
+            Ok(value) if ColorDepth::from_str(&value) == Some(ColorDepth::TrueColor) => ColorDepth::TrueColor,
+            _ => ColorDepth::Xterm256,
+        }
+    }
+}
+
+// A rectangle of the terminal to draw in instead of the whole of it, as
+// (column, row, columns, rows) with the status bar in its last row.
+pub type Region = (u16, u16, u16, u16);
+
+// Color vision deficiency simulated on output, to preview how a figure reads
+// for viewers with one cone type missing.
+#[derive(Copy, Clone, PartialEq)]
+pub enum Cvd {
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+impl Cvd {
+    pub fn from_str(s: &str) -> Option<Cvd> {
+        match s.to_lowercase().as_str() {
+            "protanopia" | "protan" => Some(Cvd::Protanopia),
+            "deuteranopia" | "deutan" => Some(Cvd::Deuteranopia),
+            "tritanopia" | "tritan" => Some(Cvd::Tritanopia),
+            _ => None,
+        }
+    }
+
+    // Full-severity matrices from Machado et al. (2009), for linear RGB.
+    fn matrix(self) -> [[f32; 3]; 3] {
+        match self {
+            Cvd::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            Cvd::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            Cvd::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+
+    // How a color appears with this deficiency.
+    pub fn simulate(self, color: Rgb) -> Rgb {
+        let linear = |v: u8| (v as f32 / 255.).powf(2.2);
+        let encoded = |v: f32| (v.clamp(0., 1.).powf(1. / 2.2) * 255.).round() as u8;
+        let rgb = [linear(color.r), linear(color.g), linear(color.b)];
+        let [r, g, b] = self.matrix().map(|row| row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2]);
+        Rgb::new(encoded(r), encoded(g), encoded(b))
+    }
+}
+
 // RGB color for a pixel
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Rgb {
     pub r: u8,
     pub g: u8,
@@ -31,6 +131,64 @@ impl Rgb {
     pub fn black() -> Rgb {
         Rgb { r: 0, g: 0, b: 0 }
     }
+
+    // Closest xterm-256 palette index: the nearer of the 6x6x6 color cube
+    // entry and the gray ramp entry (the first 16 vary between terminals).
+    pub fn xterm256_index(&self) -> u8 {
+        const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+        let nearest_level = |v: u8| {
+            (0..6).min_by_key(|&i| (CUBE_LEVELS[i] as i32 - v as i32).abs()).unwrap()
+        };
+        let distance = |other: Rgb| {
+            (self.r as i32 - other.r as i32).pow(2) + (self.g as i32 - other.g as i32).pow(2) + (self.b as i32 - other.b as i32).pow(2)
+        };
+
+        let (r, g, b) = (nearest_level(self.r), nearest_level(self.g), nearest_level(self.b));
+        let cube = Rgb::new(CUBE_LEVELS[r], CUBE_LEVELS[g], CUBE_LEVELS[b]);
+
+        // Grays run from 8 to 238 in steps of 10.
+        let average = (self.r as u32 + self.g as u32 + self.b as u32) / 3;
+        let gray_step = (average.saturating_sub(3) / 10).min(23) as u8;
+        let gray_level = 8 + 10 * gray_step;
+
+        if distance(Rgb::new(gray_level, gray_level, gray_level)) < distance(cube) {
+            232 + gray_step
+        } else {
+            16 + 36 * r as u8 + 6 * g as u8 + b as u8
+        }
+    }
+
+    // Fully saturated color for a hue in [0, 1).
+    pub fn from_hue(hue: f32) -> Rgb {
+        let h = hue.rem_euclid(1.0) * 6.0;
+        let x = ((1.0 - (h % 2.0 - 1.0).abs()) * 255.0) as u8;
+        match h as u32 {
+            0 => Rgb::new(255, x, 0),
+            1 => Rgb::new(x, 255, 0),
+            2 => Rgb::new(0, 255, x),
+            3 => Rgb::new(0, x, 255),
+            4 => Rgb::new(x, 0, 255),
+            _ => Rgb::new(255, 0, x),
+        }
+    }
+
+    // Multiply every channel, saturating at 255.
+    pub fn scale(&self, factor: f32) -> Rgb {
+        Rgb::new(
+            (self.r as f32 * factor).min(255.0) as u8,
+            (self.g as f32 * factor).min(255.0) as u8,
+            (self.b as f32 * factor).min(255.0) as u8,
+        )
+    }
+
+    // Linearly interpolate towards another color.
+    pub fn lerp(&self, other: Rgb, t: f32) -> Rgb {
+        Rgb::new(
+            ((1.0 - t) * self.r as f32 + t * other.r as f32) as u8,
+            ((1.0 - t) * self.g as f32 + t * other.g as f32) as u8,
+            ((1.0 - t) * self.b as f32 + t * other.b as f32) as u8,
+        )
+    }
 }
 
 // Setup ability to get dimensions out of matrix arrays.
@@ -112,24 +270,68 @@ impl Point {
     }
 }
 
-// Cell with on/off and color
+// Cell with on/off, color and camera-space depth of what was drawn there
 #[derive(Copy, Clone)]
 pub struct ColorCell {
     pub on: bool,
     pub color: Rgb,
+    pub depth: f32,
 }
 
 impl ColorCell {
     pub fn new() -> ColorCell {
-        ColorCell { on: false, color: Rgb::white() }
+        ColorCell { on: false, color: Rgb::white(), depth: f32::INFINITY }
     }
 }
 
+// Depth at `t` along a projected line between depths `start` and `end`.
+// Depth is interpolated as 1/z so it stays correct under perspective.
+pub fn interpolate_depth(start: f32, end: f32, t: f32) -> f32 {
+    1. / ((1. - t) / start + t / end)
+}
+
 // Wrapper for a "screen" to render.
 pub struct Screen {
     pub width: u16,
     pub height: u16,
     content: Vec<Vec<ColorCell>>,
+    // Characters last drawn by `render_diff`; empty forces a full redraw.
+    previous_frame: Vec<Vec<(char, Rgb)>>,
+    // Background painted behind the drawing; the terminal's own when None.
+    pub background: Option<Rgb>,
+    // The terminal's own background when it reported one, which unpainted
+    // cells blend toward instead of black.
+    pub terminal_background: Option<Rgb>,
+    // Text centered in the last whole character row of a viewport, as (pixel x
+    // offset, pixel width, pixel y where the viewport ends, label), e.g. the
+    // name of each side-by-side structure.
+    pub labels: Vec<(u16, u16, u16, String)>,
+    // Text starting just right of a pixel, e.g. a residue annotation; kept
+    // on screen by shifting it in from the edges.
+    pub annotations: Vec<(Point, String)>,
+    // Multiplier applied to drawn colors on output, for dim or washed-out terminals.
+    pub brightness: f32,
+    // Stroke width of non-antialiased lines in pixels (1 or 2).
+    pub thickness: u8,
+    // Anaglyph eye filter: drawn colors become gray shades in just these
+    // channels, added to what the other eye left in the remaining ones.
+    pub tint: Option<Rgb>,
+    // Skip over empty cells instead of writing spaces, leaving them unset so
+    // whatever is beneath (e.g. a recorder's backdrop) shows through.
+    pub transparent: bool,
+    pub color_depth: ColorDepth,
+    // Negative image: lit pixels become gaps in a field of off pixels, all
+    // drawn in the background color (white if none), which is not painted.
+    pub invert: bool,
+    // Opacity of lines in [0, 1], approximated by leaving out that fraction
+    // of their pixels so whatever lies behind shows through.
+    pub alpha: f32,
+    // Draw lines dashed, leaving out every other run of DASH_PIXELS pixels.
+    pub dashed: bool,
+    // Color vision deficiency applied to every color written out.
+    pub cvd: Option<Cvd>,
+    // Part of the terminal drawn in, leaving the rest untouched.
+    pub region: Option<Region>,
 }
 
 impl Screen {
@@ -149,11 +351,31 @@ impl Screen {
         };
 
         // Create screen with initial buffer (use BrailePixel dimensions as default)
-        let width = terminal_width * 2;  // BrailePixel::WIDTH = 2
-        let height = (terminal_height.saturating_sub(1)) * 4;  // BrailePixel::HEIGHT = 4
+        let width = terminal_width.max(1) * 2;  // BrailePixel::WIDTH = 2
+        let height = terminal_height.saturating_sub(1).max(1) * 4;  // BrailePixel::HEIGHT = 4
+
+        Screen::with_size(width, height)
+    }
 
+    // Create a screen of a fixed pixel size, without touching the terminal.
+    pub fn with_size(width: u16, height: u16) -> Screen {
         Screen {
             content: vec![vec![ColorCell::new(); width as usize]; height as usize],
+            previous_frame: Vec::new(),
+            background: None,
+            terminal_background: None,
+            labels: Vec::new(),
+            annotations: Vec::new(),
+            brightness: 1.0,
+            thickness: 1,
+            tint: None,
+            transparent: false,
+            color_depth: ColorDepth::TrueColor,
+            invert: false,
+            alpha: 1.0,
+            dashed: false,
+            cvd: None,
+            region: None,
             width,
             height
         }
@@ -161,30 +383,86 @@ impl Screen {
 
     // Resize braile screen to fit terminal width and height.
     pub fn fit_to_terminal<T: Pixel>(&mut self) {
-        let (terminal_width, terminal_height) = match terminal::size() {
-            Ok(dim) => dim,
-            Err(_) => DEFAULT_TERMINAL_DIMENSIONS
+        let (terminal_width, terminal_height) = match (self.region, terminal::size()) {
+            (Some((_, _, width, height)), _) => (width, height),
+            (None, Ok(dim)) => dim,
+            (None, Err(_)) => DEFAULT_TERMINAL_DIMENSIONS
         };
 
+        // Keep at least one row and column so the buffer never degenerates.
         self.resize(
-            terminal_width * T::WIDTH as u16,
-            (terminal_height - 1) * T::HEIGHT as u16
+            terminal_width.max(1) * T::WIDTH as u16,
+            terminal_height.saturating_sub(1).max(1) * T::HEIGHT as u16
         );
     }
 
-    // Write a value with color to a coord on the screen.
-    pub fn write_color(&mut self, val: bool, point: &Point, color: Rgb) {
+    // Write a value with color to a coord on the screen, unless something
+    // nearer has already been drawn there.
+    pub fn write_color(&mut self, val: bool, point: &Point, color: Rgb, depth: f32) {
         // Fix: use >= 0 instead of > 0 to include edge pixels
         let x_in_bounds = point.x >= 0 && point.x < self.width as i32;
         let y_in_bounds = point.y >= 0 && point.y < self.height as i32;
         if x_in_bounds && y_in_bounds {
-            self.content[point.y as usize][point.x as usize] = ColorCell { on: val, color };
+            let cell = self.content[point.y as usize][point.x as usize];
+            if depth <= cell.depth {
+                let color = self.tinted(&cell, color);
+                self.content[point.y as usize][point.x as usize] = ColorCell { on: val, color, depth };
+            }
+        }
+    }
+
+    // Write one pixel of a line, unless translucency leaves it out.
+    fn write_line_pixel(&mut self, point: &Point, color: Rgb, depth: f32) {
+        if !self.dithered_out(point, depth) {
+            self.write_color(true, point, color, depth);
+        }
+    }
+
+    // Whether the pixel `step` pixels along a line falls in a gap between dashes.
+    fn dash_gap(&self, step: i32) -> bool {
+        self.dashed && (step / DASH_PIXELS) % 2 == 1
+    }
+
+    // Whether a pixel is skipped to fake translucency. The choice hashes the
+    // position, so a still view keeps the same pattern from frame to frame,
+    // and the depth, so layers behind get different holes and fill the front
+    // layer's.
+    fn dithered_out(&self, point: &Point, depth: f32) -> bool {
+        if self.alpha >= 1. {
+            return false;
+        }
+        let mut hash = (point.x as u32).wrapping_mul(0x9E37_79B1)
+            ^ (point.y as u32).wrapping_mul(0x85EB_CA77)
+            ^ depth.to_bits().wrapping_mul(0xC2B2_AE3D);
+        hash ^= hash >> 15;
+        hash = hash.wrapping_mul(0x2C1B_3C6D);
+        hash ^= hash >> 12;
+        (hash & 0xFFFF) as f32 / 65536. >= self.alpha
+    }
+
+    // Filter a color through the anaglyph tint, keeping the other eye's
+    // channels from the cell.
+    fn tinted(&self, cell: &ColorCell, color: Rgb) -> Rgb {
+        let Some(tint) = self.tint else { return color };
+        let gray = (0.299 * color.r as f32 + 0.587 * color.g as f32 + 0.114 * color.b as f32) / 255.;
+        let other = if cell.on { cell.color } else { self.backdrop() };
+        let channel = |mask: u8, own: u8| if mask > 0 { (mask as f32 * gray) as u8 } else { own };
+        Rgb::new(channel(tint.r, other.r), channel(tint.g, other.g), channel(tint.b, other.b))
+    }
+
+    // Forget depths but keep colors, so a second stereo eye draws over the
+    // first without being hidden by it.
+    pub fn reset_depth(&mut self) {
+        for row in &mut self.content {
+            for cell in row {
+                cell.depth = f32::INFINITY;
+            }
         }
     }
 
     // Write a value (white) to a coord on the screen.
-    pub fn write(&mut self, val: bool, point: &Point) {
-        self.write_color(val, point, Rgb::white());
+    pub fn write(&mut self, val: bool, point: &Point, depth: f32) {
+        self.write_color(val, point, Rgb::white(), depth);
     }
 
     // Clears the whole screen by resetting existing buffer (no allocation)
@@ -193,6 +471,7 @@ impl Screen {
             for cell in row {
                 cell.on = false;
                 cell.color = Rgb::white();
+                cell.depth = f32::INFINITY;
             }
         }
     }
@@ -209,7 +488,7 @@ impl Screen {
     }
 
     // Draw a colored line with Bresenham's line algorithm.
-    pub fn line_color(&mut self, start: &Point, end: &Point, start_color: Rgb, end_color: Rgb) {
+    pub fn line_color(&mut self, start: &Point, end: &Point, start_color: Rgb, end_color: Rgb, depth: (f32, f32)) {
         let delta_x = (end.x - start.x).abs();
         let step_x: i32 = if start.x < end.x {1} else {-1};
         let delta_y = -(end.y - start.y).abs();
@@ -221,6 +500,7 @@ impl Screen {
 
         let total_steps = (delta_x.abs() + (-delta_y).abs()).max(1) as f32;
         let mut step = 0;
+        let x_major = delta_x >= -delta_y;
 
         loop {
             // Interpolate color
@@ -231,7 +511,13 @@ impl Screen {
                 ((1.0 - t) * start_color.b as f32 + t * end_color.b as f32) as u8,
             );
 
-            self.write_color(true, &Point::new(x, y), color);
+            let depth = interpolate_depth(depth.0, depth.1, t);
+            if !self.dash_gap(step) {
+                self.write_line_pixel(&Point::new(x, y), color, depth);
+                if self.thickness > 1 {
+                    self.write_line_pixel(&thick_neighbor(x, y, x_major), color, depth);
+                }
+            }
 
             if x == end.x && y == end.y { break; }
 
@@ -248,13 +534,79 @@ impl Screen {
         }
     }
 
+    // Blend a color into a cell weighted by coverage, dimming towards the
+    // background and mixing with whatever is already drawn there.
+    fn blend_color(&mut self, point: &Point, color: Rgb, coverage: f32, depth: f32) {
+        let x_in_bounds = point.x >= 0 && point.x < self.width as i32;
+        let y_in_bounds = point.y >= 0 && point.y < self.height as i32;
+        if !x_in_bounds || !y_in_bounds || coverage <= AA_MIN_COVERAGE || self.dithered_out(point, depth) {
+            return;
+        }
+
+        let cell = self.content[point.y as usize][point.x as usize];
+        if depth > cell.depth {
+            return;
+        }
+        let base = if cell.on { cell.color } else { self.backdrop() };
+        let color = self.tinted(&cell, color);
+        self.content[point.y as usize][point.x as usize] = ColorCell { on: true, color: base.lerp(color, coverage), depth };
+    }
+
+    // Draw a colored antialiased line (Xiaolin Wu) between subpixel coordinates.
+    pub fn line_color_aa(&mut self, start: (f32, f32), end: (f32, f32), start_color: Rgb, end_color: Rgb, depth: (f32, f32)) {
+        let (mut x0, mut y0, mut x1, mut y1) = (start.0, start.1, end.0, end.1);
+        let (mut color0, mut color1) = (start_color, end_color);
+        let (mut depth0, mut depth1) = depth;
+
+        // Walk along the major axis.
+        let steep = (y1 - y0).abs() > (x1 - x0).abs();
+        if steep {
+            mem::swap(&mut x0, &mut y0);
+            mem::swap(&mut x1, &mut y1);
+        }
+        if x0 > x1 {
+            mem::swap(&mut x0, &mut x1);
+            mem::swap(&mut y0, &mut y1);
+            mem::swap(&mut color0, &mut color1);
+            mem::swap(&mut depth0, &mut depth1);
+        }
+
+        let delta_x = x1 - x0;
+        let gradient = if delta_x == 0. { 1. } else { (y1 - y0) / delta_x };
+        let x_start = x0.round() as i32;
+        let x_end = x1.round() as i32;
+        let total_steps = (x_end - x_start).max(1) as f32;
+
+        let mut y = y0 + gradient * (x_start as f32 - x0);
+        for x in x_start..=x_end {
+            if self.dash_gap(x - x_start) {
+                y += gradient;
+                continue;
+            }
+            let t = (x - x_start) as f32 / total_steps;
+            let color = color0.lerp(color1, t);
+            let depth = interpolate_depth(depth0, depth1, t);
+            let y_floor = y.floor();
+            let frac = y - y_floor;
+
+            for (offset, coverage) in [(0, 1. - frac), (1, frac)] {
+                let minor = y_floor as i32 + offset;
+                let point = if steep { Point::new(minor, x) } else { Point::new(x, minor) };
+                self.blend_color(&point, color, coverage, depth);
+            }
+            y += gradient;
+        }
+    }
+
     // Draw a colored line clipped to specified bounds
+    #[allow(clippy::too_many_arguments)]
     pub fn line_color_clipped(
         &mut self,
         start: &Point,
         end: &Point,
         start_color: Rgb,
         end_color: Rgb,
+        depth: (f32, f32),
         clip_x_min: i32,
         clip_x_max: i32,
         clip_y_min: i32,
@@ -271,17 +623,27 @@ impl Screen {
 
         let total_steps = (delta_x.abs() + (-delta_y).abs()).max(1) as f32;
         let mut step = 0;
+        let x_major = delta_x >= -delta_y;
+        let in_clip = |p: &Point| p.x >= clip_x_min && p.x < clip_x_max && p.y >= clip_y_min && p.y < clip_y_max;
 
         loop {
             // Only draw if within clip bounds
-            if x >= clip_x_min && x < clip_x_max && y >= clip_y_min && y < clip_y_max {
-                let t = step as f32 / total_steps;
-                let color = Rgb::new(
-                    ((1.0 - t) * start_color.r as f32 + t * end_color.r as f32) as u8,
-                    ((1.0 - t) * start_color.g as f32 + t * end_color.g as f32) as u8,
-                    ((1.0 - t) * start_color.b as f32 + t * end_color.b as f32) as u8,
-                );
-                self.write_color(true, &Point::new(x, y), color);
+            let t = step as f32 / total_steps;
+            let color = Rgb::new(
+                ((1.0 - t) * start_color.r as f32 + t * end_color.r as f32) as u8,
+                ((1.0 - t) * start_color.g as f32 + t * end_color.g as f32) as u8,
+                ((1.0 - t) * start_color.b as f32 + t * end_color.b as f32) as u8,
+            );
+            let depth = interpolate_depth(depth.0, depth.1, t);
+            let point = Point::new(x, y);
+            let neighbor = thick_neighbor(x, y, x_major);
+            if !self.dash_gap(step) {
+                if in_clip(&point) {
+                    self.write_line_pixel(&point, color, depth);
+                }
+                if self.thickness > 1 && in_clip(&neighbor) {
+                    self.write_line_pixel(&neighbor, color, depth);
+                }
             }
 
             if x == end.x && y == end.y { break; }
@@ -299,8 +661,9 @@ impl Screen {
         }
     }
 
-    // Render the screen with colors and status bar
-    pub fn render_with_status<PixelType: Pixel>(&self, status: &str) {
+    // The whole frame with a bottom bar made of text spans, each optionally
+    // drawn in its own color (e.g. a legend).
+    fn full_frame<PixelType: Pixel>(&self, spans: &[Span]) -> Vec<u8> {
         let pixel_height = PixelType::HEIGHT;
         let pixel_width = PixelType::WIDTH;
         let real_row_width = self.width.div_ceil(pixel_width as u16) as usize;
@@ -313,109 +676,387 @@ impl Screen {
         // Move cursor to home position and reset color state
         buffer.extend_from_slice(b"\x1b[H\x1b[0m");
 
-        // Pre-allocate row buffers outside the loop
-        let mut real_row: Vec<(PixelType, Rgb)> = vec![(PixelType::new(), Rgb::black()); real_row_width];
-        let mut color_accum: Vec<(u32, u32, u32, u32)> = vec![(0, 0, 0, 0); real_row_width];
+        if self.region.is_some() {
+            // Each row moves to its place in the region; nothing may be
+            // cleared past the region's edge.
+            self.write_rows::<PixelType>(&mut buffer, true, self.transparent, b"");
+            self.write_cursor_move(&mut buffer, 0, num_rows);
+        } else if self.transparent {
+            // No clear here: skipped cells must keep the terminal's content.
+            self.write_rows::<PixelType>(&mut buffer, true, true, b"\r\n");
+        } else {
+            self.write_rows::<PixelType>(&mut buffer, true, false, b"\x1b[K\r\n");
+        }
 
-        let mut current_color: Option<Rgb> = None;
-        let mut row_idx = 0;
+        self.write_status(&mut buffer, spans, real_row_width);
+        buffer
+    }
+
+    // Render only the characters that changed since the last call, moving the
+    // cursor to each run of changes. Falls back to a full redraw on the first
+    // frame and after a resize.
+    pub fn render_diff<PixelType: Pixel>(&mut self, spans: &[Span]) {
+        let buffer = self.diff_frame::<PixelType>(spans);
+
+        // Write entire frame at once with lock held
+        let stdout = io::stdout();
+        let mut handle = stdout.lock();
+        let _ = handle.write_all(&buffer);
+        let _ = handle.flush();
+    }
 
-        while row_idx < self.height as usize {
-            // Reset buffers instead of reallocating
-            for i in 0..real_row_width {
-                real_row[i] = (PixelType::new(), Rgb::black());
-                color_accum[i] = (0, 0, 0, 0);
+    // The output `render_diff` writes, for sinks other than stdout.
+    pub fn diff_frame<PixelType: Pixel>(&mut self, spans: &[Span]) -> Vec<u8> {
+        let pixel_height = PixelType::HEIGHT;
+        let real_row_width = self.width.div_ceil(PixelType::WIDTH as u16) as usize;
+        let num_rows = self.height.div_ceil(pixel_height as u16) as usize;
+
+        if real_row_width < MIN_USABLE_DIMENSIONS.0 as usize || num_rows < MIN_USABLE_DIMENSIONS.1 as usize {
+            self.previous_frame.clear();
+            let msg: String = "terminal too small".chars().take(real_row_width).collect();
+            if self.region.is_none() {
+                return format!("\x1b[H\x1b[0m\x1b[2J{}", msg).into_bytes();
             }
+            let mut buffer = b"\x1b[0m".to_vec();
+            self.write_cursor_move(&mut buffer, 0, 0);
+            buffer.extend_from_slice(msg.as_bytes());
+            return buffer;
+        }
 
-            for subpixel_y in 0..pixel_height {
-                let y = row_idx + subpixel_y;
-                if y >= self.height as usize {
-                    break;
+        let frame: Vec<Vec<(char, Rgb)>> = (0..num_rows)
+            .into_par_iter()
+            .map(|row| self.row_cells::<PixelType>(row * pixel_height))
+            .collect();
+
+        let same_size = self.previous_frame.len() == num_rows
+            && self.previous_frame.first().is_some_and(|row| row.len() == real_row_width);
+        if !same_size {
+            self.previous_frame = frame;
+            return self.full_frame::<PixelType>(spans);
+        }
+
+        let mut buffer = Vec::<u8>::new();
+        let mut current_color: Option<Rgb> = None;
+        let mut cursor: Option<(usize, usize)> = None;
+        if let Some(background) = self.painted_background() {
+            write_bg_color(&mut buffer, background, self.color_depth);
+        }
+
+        for (y, (row, previous_row)) in frame.iter().zip(&self.previous_frame).enumerate() {
+            for (x, (cell, previous_cell)) in row.iter().zip(previous_row).enumerate() {
+                if cell == previous_cell {
+                    continue;
+                }
+                if cursor != Some((x, y)) {
+                    self.write_cursor_move(&mut buffer, x, y);
                 }
 
-                let row = &self.content[y];
-                for real_x in 0..real_row_width {
-                    for subpixel_x in 0..pixel_width {
-                        let x = real_x * pixel_width + subpixel_x;
-                        if x >= self.width as usize {
-                            break;
-                        }
-
-                        let cell = &row[x];
-                        real_row[real_x].0[subpixel_y][subpixel_x] = cell.on;
-                        if cell.on {
-                            color_accum[real_x].0 += cell.color.r as u32;
-                            color_accum[real_x].1 += cell.color.g as u32;
-                            color_accum[real_x].2 += cell.color.b as u32;
-                            color_accum[real_x].3 += 1;
-                        }
-                    }
+                let (ch, color) = *cell;
+                if self.transparent && is_blank(ch) {
+                    // Erase in place rather than paint a space; the cursor stays put.
+                    buffer.extend_from_slice(b"\x1b[X");
+                    cursor = Some((x, y));
+                    continue;
+                }
+                if ch != ' ' && current_color != Some(color) {
+                    write_fg_color(&mut buffer, color, self.color_depth);
+                    current_color = Some(color);
                 }
+                let mut char_buf = [0u8; 4];
+                buffer.extend_from_slice(ch.encode_utf8(&mut char_buf).as_bytes());
+                cursor = Some((x + 1, y));
             }
+        }
+
+        // The status bar changes most frames (fps), so always rewrite it.
+        self.write_cursor_move(&mut buffer, 0, num_rows);
+        self.write_status(&mut buffer, spans, real_row_width);
 
-            // Compute average colors
-            for i in 0..real_row_width {
-                if color_accum[i].3 > 0 {
-                    let count = color_accum[i].3;
-                    real_row[i].1 = Rgb::new(
-                        (color_accum[i].0 / count) as u8,
-                        (color_accum[i].1 / count) as u8,
-                        (color_accum[i].2 / count) as u8,
-                    );
+        self.previous_frame = frame;
+        buffer
+    }
+
+    // Render the screen as standalone text for a file: ANSI colored, or plain
+    // characters only. Every line ends with a reset so files concatenate cleanly.
+    pub fn export<PixelType: Pixel>(&self, ansi: bool) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        let line_end: &[u8] = if ansi { b"\x1b[0m\n" } else { b"\n" };
+        self.write_rows::<PixelType>(&mut buffer, ansi, false, line_end);
+        buffer
+    }
+
+    // Append the pixel rows to a buffer, optionally with truecolor escapes and
+    // cursor-forward moves over empty cells. Rows are built in parallel and joined in order.
+    fn write_rows<PixelType: Pixel>(&self, buffer: &mut Vec<u8>, ansi: bool, skip_blank: bool, line_end: &[u8]) {
+        let pixel_height = PixelType::HEIGHT;
+        let num_rows = (self.height as usize).div_ceil(pixel_height);
+
+        let rows: Vec<Vec<u8>> = (0..num_rows)
+            .into_par_iter()
+            .map(|row| self.build_row::<PixelType>(row * pixel_height, ansi, skip_blank, line_end))
+            .collect();
+
+        for row in rows {
+            buffer.extend_from_slice(&row);
+        }
+    }
+
+    // Build the output for the character row starting at pixel row `row_idx`.
+    // Each row sets its own first color since color state can't carry across threads.
+    fn build_row<PixelType: Pixel>(&self, row_idx: usize, ansi: bool, skip_blank: bool, line_end: &[u8]) -> Vec<u8> {
+        let cells = self.row_cells::<PixelType>(row_idx);
+        let mut buffer = Vec::<u8>::with_capacity(cells.len() * 30 + line_end.len());
+        let mut current_color: Option<Rgb> = None;
+        if let Some((_, _, width, _)) = self.region {
+            self.write_cursor_move(&mut buffer, 0, row_idx / PixelType::HEIGHT);
+            if skip_blank {
+                // Skipped cells would keep the last frame; erase the row first.
+                let _ = write!(buffer, "\x1b[{}X", width);
+            }
+        }
+        if let (true, Some(background)) = (ansi, self.painted_background()) {
+            write_bg_color(&mut buffer, background, self.color_depth);
+        }
+
+        // Empty cells not yet skipped over; trailing ones need no move at all.
+        let mut blank_run = 0;
+        for (ch, color) in cells {
+            if skip_blank && is_blank(ch) {
+                blank_run += 1;
+                continue;
+            }
+            if blank_run > 0 {
+                let _ = write!(buffer, "\x1b[{}C", blank_run);
+                blank_run = 0;
+            }
+            if ch != ' ' {
+                // Only change color if different
+                if ansi && current_color != Some(color) {
+                    write_fg_color(&mut buffer, color, self.color_depth);
+                    current_color = Some(color);
                 }
+                let mut char_buf = [0u8; 4];
+                buffer.extend_from_slice(ch.encode_utf8(&mut char_buf).as_bytes());
+            } else {
+                buffer.push(b' ');
+            }
+        }
+
+        buffer.extend_from_slice(line_end);
+        buffer
+    }
+
+    // Characters and averaged colors for the character row starting at pixel row `row_idx`.
+    fn row_cells<PixelType: Pixel>(&self, row_idx: usize) -> Vec<(char, Rgb)> {
+        let pixel_height = PixelType::HEIGHT;
+        let pixel_width = PixelType::WIDTH;
+        let real_row_width = self.width.div_ceil(pixel_width as u16) as usize;
+
+        let mut real_row: Vec<(PixelType, Rgb)> = vec![(PixelType::new(), Rgb::black()); real_row_width];
+        let mut color_accum: Vec<(u32, u32, u32, u32)> = vec![(0, 0, 0, 0); real_row_width];
+
+        for subpixel_y in 0..pixel_height {
+            let y = row_idx + subpixel_y;
+            if y >= self.height as usize {
+                break;
             }
 
-            // Build output for this row
-            for i in 0..real_row_width {
-                let (ref pixel, ref color) = real_row[i];
-                let ch = pixel.to_char();
-                if ch != ' ' {
-                    // Only change color if different
-                    if current_color.map_or(true, |c| c.r != color.r || c.g != color.g || c.b != color.b) {
-                        // Manual formatting to avoid allocation
-                        buffer.extend_from_slice(b"\x1b[38;2;");
-                        write_u8_to_buffer(&mut buffer, color.r);
-                        buffer.push(b';');
-                        write_u8_to_buffer(&mut buffer, color.g);
-                        buffer.push(b';');
-                        write_u8_to_buffer(&mut buffer, color.b);
-                        buffer.push(b'm');
-                        current_color = Some(*color);
+            let row = &self.content[y];
+            for real_x in 0..real_row_width {
+                for subpixel_x in 0..pixel_width {
+                    let x = real_x * pixel_width + subpixel_x;
+                    if x >= self.width as usize {
+                        break;
+                    }
+
+                    let cell = &row[x];
+                    real_row[real_x].0[subpixel_y][subpixel_x] = cell.on != self.invert;
+                    if cell.on {
+                        color_accum[real_x].0 += cell.color.r as u32;
+                        color_accum[real_x].1 += cell.color.g as u32;
+                        color_accum[real_x].2 += cell.color.b as u32;
+                        color_accum[real_x].3 += 1;
                     }
-                    let mut char_buf = [0u8; 4];
-                    buffer.extend_from_slice(ch.encode_utf8(&mut char_buf).as_bytes());
-                } else {
-                    buffer.push(b' ');
                 }
             }
+        }
+
+        // Compute average colors
+        for (cell, accum) in real_row.iter_mut().zip(&color_accum) {
+            if self.invert {
+                cell.1 = self.simulated(self.background.unwrap_or(Rgb::white()).scale(self.brightness));
+            } else if accum.3 > 0 {
+                let count = accum.3;
+                cell.1 = self.simulated(Rgb::new((accum.0 / count) as u8, (accum.1 / count) as u8, (accum.2 / count) as u8)
+                    .scale(self.brightness));
+            }
+        }
 
-            // Clear to end of line and newline
-            buffer.extend_from_slice(b"\x1b[K\r\n");
+        let mut cells: Vec<(char, Rgb)> = real_row.iter().map(|(pixel, color)| (pixel.to_char(), *color)).collect();
+        self.overlay_labels(&mut cells, row_idx / pixel_height, pixel_width, pixel_height);
+        self.overlay_annotations(&mut cells, row_idx / pixel_height, pixel_width, pixel_height);
+        cells
+    }
 
-            row_idx += pixel_height;
+    // Write the annotations anchored in this character row, clamped so the
+    // whole text stays on screen.
+    fn overlay_annotations(&self, cells: &mut [(char, Rgb)], row: usize, pixel_width: usize, pixel_height: usize) {
+        let rows = (self.height as usize).div_ceil(pixel_height);
+        for (point, text) in &self.annotations {
+            if (point.y.max(0) as usize / pixel_height).min(rows - 1) != row {
+                continue;
+            }
+            let text: Vec<char> = text.chars().take(cells.len()).collect();
+            let start = (point.x.max(0) as usize / pixel_width + 1).min(cells.len() - text.len());
+            for (cell, ch) in cells[start..].iter_mut().zip(text) {
+                *cell = (ch, ANNOTATION_COLOR);
+            }
         }
+    }
 
-        // Reset color and add centered status bar
+    // Append a cursor move to a zero-based character cell of the drawing,
+    // offset into the region when there is one.
+    fn write_cursor_move(&self, buffer: &mut Vec<u8>, x: usize, y: usize) {
+        let (left, top) = self.region.map_or((0, 0), |(left, top, ..)| (left as usize, top as usize));
+        let _ = write!(buffer, "\x1b[{};{}H", top + y + 1, left + x + 1);
+    }
+
+    // Reset color and append a status bar of (optionally colored) spans,
+    // centered in the given width. Within a region the bar is padded to the
+    // width instead of clearing to the end of the terminal line.
+    fn write_status(&self, buffer: &mut Vec<u8>, spans: &[Span], width: usize) {
         buffer.extend_from_slice(b"\x1b[0m");
-        let terminal_width = real_row_width;
-        let status_len = status.chars().count();
-        let padding = if terminal_width > status_len {
-            (terminal_width - status_len) / 2
+        let status_len: usize = spans.iter().map(|(text, _)| visible_width(text)).sum();
+        let padding = if width > status_len {
+            (width - status_len) / 2
         } else {
             0
         };
-        for _ in 0..padding {
-            buffer.push(b' ');
+        buffer.resize(buffer.len() + padding, b' ');
+        for (text, color) in spans {
+            if let Some(color) = color {
+                write_fg_color(buffer, self.simulated(*color), self.color_depth);
+            }
+            buffer.extend_from_slice(text.as_bytes());
+            if color.is_some() {
+                buffer.extend_from_slice(b"\x1b[0m");
+            }
+        }
+        if self.region.is_some() {
+            buffer.resize(buffer.len() + width.saturating_sub(padding + status_len), b' ');
+        } else {
+            buffer.extend_from_slice(b"\x1b[K");
         }
-        buffer.extend_from_slice(status.as_bytes());
-        buffer.extend_from_slice(b"\x1b[K");
+    }
 
-        // Write entire frame at once with lock held
-        let stdout = io::stdout();
-        let mut handle = stdout.lock();
-        let _ = handle.write_all(&buffer);
-        let _ = handle.flush();
+    // Color behind an unlit pixel: the painted background, else the
+    // terminal's, assumed black when unknown.
+    fn backdrop(&self) -> Rgb {
+        self.background.or(self.terminal_background).unwrap_or(Rgb::black())
+    }
+
+    // Background color written behind the cells; an inverted image draws with it instead.
+    fn painted_background(&self) -> Option<Rgb> {
+        if self.invert { None } else { self.background.map(|color| self.simulated(color)) }
     }
+
+    // A color as written out under the simulated color vision deficiency.
+    fn simulated(&self, color: Rgb) -> Rgb {
+        self.cvd.map_or(color, |cvd| cvd.simulate(color))
+    }
+
+    // Write the labels whose viewport's last whole character row is this
+    // one, each centered within its span of the row and truncated to fit.
+    fn overlay_labels(&self, cells: &mut [(char, Rgb)], row: usize, pixel_width: usize, pixel_height: usize) {
+        let rows = (self.height as usize).div_ceil(pixel_height);
+        for (x, width, bottom, label) in &self.labels {
+            if (*bottom as usize / pixel_height).clamp(1, rows) != row + 1 {
+                continue;
+            }
+            let start = *x as usize / pixel_width;
+            let width = (*width as usize / pixel_width).min(cells.len().saturating_sub(start));
+            let text: Vec<char> = label.chars().take(width).collect();
+            let offset = start + (width - text.len()) / 2;
+            for (cell, ch) in cells[offset..].iter_mut().zip(text) {
+                *cell = (ch, LABEL_COLOR);
+            }
+        }
+    }
+}
+
+// Pixel next to (x, y) across a line's major axis, used to thicken it.
+fn thick_neighbor(x: i32, y: i32, x_major: bool) -> Point {
+    if x_major { Point::new(x, y + 1) } else { Point::new(x + 1, y) }
+}
+
+// Whether a character cell has no pixels set (blocks use a space, braille its empty pattern).
+fn is_blank(ch: char) -> bool {
+    ch == ' ' || ch == '\u{2800}'
+}
+
+// Append a foreground color escape.
+fn write_fg_color(buffer: &mut Vec<u8>, color: Rgb, depth: ColorDepth) {
+    buffer.extend_from_slice(b"\x1b[38;");
+    write_color_code(buffer, color, depth);
+}
+
+// Append a background color escape.
+fn write_bg_color(buffer: &mut Vec<u8>, color: Rgb, depth: ColorDepth) {
+    buffer.extend_from_slice(b"\x1b[48;");
+    write_color_code(buffer, color, depth);
+}
+
+// The color part of an SGR escape: `2;r;g;bm`, or `5;Nm` for a palette index.
+fn write_color_code(buffer: &mut Vec<u8>, color: Rgb, depth: ColorDepth) {
+    // Manual formatting to avoid allocation
+    match depth {
+        ColorDepth::TrueColor => {
+            buffer.extend_from_slice(b"2;");
+            write_u8_to_buffer(buffer, color.r);
+            buffer.push(b';');
+            write_u8_to_buffer(buffer, color.g);
+            buffer.push(b';');
+            write_u8_to_buffer(buffer, color.b);
+        }
+        ColorDepth::Xterm256 => {
+            buffer.extend_from_slice(b"5;");
+            write_u8_to_buffer(buffer, color.xterm256_index());
+        }
+    }
+    buffer.push(b'm');
+}
+
+// Foreground (or background) escape as a string, for output outside the screen.
+pub fn color_escape(color: Rgb, depth: ColorDepth, background: bool) -> String {
+    let mut buffer = Vec::new();
+    if background {
+        write_bg_color(&mut buffer, color, depth);
+    } else {
+        write_fg_color(&mut buffer, color, depth);
+    }
+    String::from_utf8_lossy(&buffer).into_owned()
+}
+
+// Columns a string occupies on screen: escape sequences take none and wide
+// glyphs (e.g. CJK) take two.
+pub fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\x1b' {
+            // CSI sequences run to a final byte in @..~; others are one character.
+            if chars.next() == Some('[') {
+                for ch in chars.by_ref() {
+                    if ('@'..='~').contains(&ch) {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        width += ch.width().unwrap_or(0);
+    }
+    width
 }
 
 // Helper to write u8 as decimal without allocation
@@ -431,3 +1072,15 @@ fn write_u8_to_buffer(buffer: &mut Vec<u8>, n: u8) {
         buffer.push(b'0' + n);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visible_width_skips_escapes_and_counts_wide_glyphs() {
+        assert_eq!(visible_width("\x1b[1;38;2;255;0;0m蛋白\x1b[0m A"), 6);
+        assert_eq!(visible_width("\x1b[2K\x1b[10;5Hタンパク質"), 10);
+        assert_eq!(visible_width("1CRN \x1b[7m結晶\x1b[27m"), 9);
+    }
+}
@@ -0,0 +1,52 @@
+use std::*;
+use std::fmt::Write;
+
+use crate::screen::Rgb;
+use crate::three::ProjectedEdge;
+
+// Format a color as an SVG hex string.
+fn hex(color: &Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+// Build an SVG document from projected edges, drawing far edges first so
+// nearer ones paint over them. Each edge gets its own gradient stroke.
+pub fn to_svg(mut edges: Vec<ProjectedEdge>, width: u32, height: u32, background: Rgb) -> String {
+    edges.sort_by(|a, b| b.depth.partial_cmp(&a.depth).unwrap_or(cmp::Ordering::Equal));
+
+    let mut defs = String::new();
+    let mut lines = String::new();
+
+    for (i, edge) in edges.iter().enumerate() {
+        let (x1, y1) = edge.start;
+        let (x2, y2) = edge.end;
+
+        let stroke = if edge.start_color == edge.end_color {
+            hex(&edge.start_color)
+        } else {
+            let _ = writeln!(
+                defs,
+                "<linearGradient id=\"g{}\" gradientUnits=\"userSpaceOnUse\" x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\">\
+<stop offset=\"0\" stop-color=\"{}\"/><stop offset=\"1\" stop-color=\"{}\"/></linearGradient>",
+                i, x1, y1, x2, y2, hex(&edge.start_color), hex(&edge.end_color)
+            );
+            format!("url(#g{})", i)
+        };
+
+        let dash = if edge.dashed { " stroke-dasharray=\"2 2\"" } else { "" };
+        let _ = writeln!(
+            lines,
+            "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"{}\"{}/>",
+            x1, y1, x2, y2, stroke, dash
+        );
+    }
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n\
+<rect width=\"100%\" height=\"100%\" fill=\"{bg}\"/>\n\
+<defs>\n{defs}</defs>\n\
+<g stroke-width=\"1\" stroke-linecap=\"round\">\n{lines}</g>\n\
+</svg>\n",
+        w = width, h = height, bg = hex(&background), defs = defs, lines = lines
+    )
+}
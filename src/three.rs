@@ -4,6 +4,13 @@
 use crate::{model, screen};
 use crate::screen::Rgb;
 
+const RIBBON_HALF_WIDTH: f32 = 0.4; // world units (angstroms) from a ribbon's center line to its side strands
+const RIBBON_MIN_OFFSET: f32 = 0.75; // screen pixels; nearer strands would merge with the center line
+const RIBBON_MAX_OFFSET: f32 = 1.5; // screen pixels, so strands stay touching when zoomed in
+// Sides of the center line the strands are drawn on (+1 towards the top of the
+// screen), and their shades, as if lit from above.
+const RIBBON_STRANDS: [(f32, f32); 2] = [(1., 0.8), (-1., 0.55)];
+
 // Simple 3d point wrapper.
 #[derive(Copy, Clone)]
 pub struct Point {
@@ -17,6 +24,21 @@ impl Point {
     pub fn new(x: f32, y: f32, z: f32) -> Point {
         Point { x, y, z }
     }
+
+    // Euclidean distance to another point.
+    pub fn distance(&self, other: &Point) -> f32 {
+        ((self.x - other.x).powi(2) + (self.y - other.y).powi(2) + (self.z - other.z).powi(2)).sqrt()
+    }
+}
+
+// An edge projected onto a 2d canvas, with its mean camera-space depth.
+pub struct ProjectedEdge {
+    pub start: (f32, f32),
+    pub end: (f32, f32),
+    pub start_color: Rgb,
+    pub end_color: Rgb,
+    pub depth: f32,
+    pub dashed: bool,
 }
 
 pub struct Camera {
@@ -37,7 +59,22 @@ pub struct Camera {
     pub viewport_fov: f32,
 
     // Screen to render.
-    pub screen: screen::Screen
+    pub screen: screen::Screen,
+
+    // Draw edges with antialiased lines.
+    pub antialias: bool,
+
+    // Draw shaded strands beside each edge, for a tube-like look.
+    pub ribbon: bool,
+
+    // Stereo eye: sideways shift of the camera along its own x axis, with
+    // the views of both eyes agreeing at `convergence` depth.
+    pub eye_offset: f32,
+    pub convergence: f32,
+
+    // Camera-space depth range kept on screen (near, far), for peeling away
+    // the front or back of a model. The near plane still applies under it.
+    pub slab: Option<(f32, f32)>
 }
 
 #[allow(dead_code)]
@@ -47,12 +84,18 @@ impl Camera {
         coordinates: Point,
         yaw: f32, pitch: f32, roll: f32,
         viewport_distance: f32, viewport_fov: f32,
+        screen: screen::Screen,
     ) -> Camera {
         Camera {
             coordinates,
             yaw, pitch, roll,
             viewport_distance, viewport_fov,
-            screen: screen::Screen::new()
+            screen,
+            antialias: false,
+            ribbon: false,
+            eye_offset: 0.,
+            convergence: 1.,
+            slab: None
         }
     }
 
@@ -82,25 +125,129 @@ impl Camera {
         let unrolled_y = unpitched_x * s_roll + unpitched_y * c_roll;
         let unrolled_z = unpitched_z;
 
-        Point::new(unrolled_x, unrolled_y, unrolled_z)
+        // Shift for the stereo eye, skewing so parallax vanishes at convergence.
+        let eye_x = unrolled_x - self.eye_offset * (1. - unrolled_z / self.convergence);
+
+        Point::new(eye_x, unrolled_y, unrolled_z)
     }
 
     // Convert camera to screen coordinates.
     fn camera_to_screen(&self, point: &Point) -> screen::Point {
+        let (screen_x, screen_y) = self.camera_to_screen_exact(point);
+
+        // Round.
+        screen::Point::new(screen_x.round() as i32, screen_y.round() as i32)
+    }
+
+    // Convert camera to unrounded screen coordinates.
+    fn camera_to_screen_exact(&self, point: &Point) -> (f32, f32) {
+        self.camera_to_canvas(point, self.screen.width as f32, self.screen.height as f32)
+    }
+
+    // Convert camera to coordinates on a canvas of arbitrary size.
+    fn camera_to_canvas(&self, point: &Point, width: f32, height: f32) -> (f32, f32) {
         // Project onto viewport coordinates.
         let viewport_x = point.x * self.viewport_distance / point.z;
         let viewport_y = point.y * self.viewport_distance / point.z;
 
-        // Compute viewport width and height based on screen width, height, and fov.
+        // Compute viewport width and height based on canvas width, height, and fov.
         let viewport_width = 2. * self.viewport_distance * (self.viewport_fov / 2.).tan();
-        let viewport_height = (self.screen.height as f32 / self.screen.width as f32) * viewport_width;
+        let viewport_height = (height / width) * viewport_width;
 
-        // Project to screen coordinates.
-        let screen_x = (viewport_x / viewport_width + 0.5) * self.screen.width as f32;
-        let screen_y = (1.0 - (viewport_y / viewport_height + 0.5)) * self.screen.height as f32;
+        // Project to canvas coordinates.
+        let canvas_x = (viewport_x / viewport_width + 0.5) * width;
+        let canvas_y = (1.0 - (viewport_y / viewport_height + 0.5)) * height;
 
-        // Round.
-        screen::Point::new(screen_x.round() as i32, screen_y.round() as i32)
+        (canvas_x, canvas_y)
+    }
+
+    // Depths drawn: from the near plane (or the slab's front, if deeper) to
+    // the slab's back.
+    fn depth_range(&self) -> (f32, f32) {
+        match self.slab {
+            Some((near, far)) => (near.max(self.viewport_distance), far),
+            None => (self.viewport_distance, f32::INFINITY),
+        }
+    }
+
+    // Clip a camera-space segment to the drawn depth range, interpolating
+    // color at the cuts. Returns None if no part of it is in range.
+    fn clip_depth(&self, camera_start: Point, camera_end: Point, start_color: Rgb, end_color: Rgb) -> Option<(Point, Point, Rgb, Rgb)> {
+        let (near, far) = self.depth_range();
+        let delta_z = camera_end.z - camera_start.z;
+
+        // Parameter range along the segment that lies within [near, far].
+        let (mut t0, mut t1) = (0f32, 1f32);
+        if delta_z == 0. {
+            if camera_start.z < near || camera_start.z > far { return None; }
+        } else {
+            let (t_near, t_far) = ((near - camera_start.z) / delta_z, (far - camera_start.z) / delta_z);
+            t0 = t0.max(t_near.min(t_far));
+            t1 = t1.min(t_near.max(t_far));
+            if t0 > t1 { return None; }
+        }
+
+        let at = |t: f32| Point::new(
+            camera_start.x + t * (camera_end.x - camera_start.x),
+            camera_start.y + t * (camera_end.y - camera_start.y),
+            camera_start.z + t * delta_z,
+        );
+        Some((at(t0), at(t1), start_color.lerp(end_color, t0), start_color.lerp(end_color, t1)))
+    }
+
+    // Whether a camera-space depth is within the drawn range.
+    fn in_depth_range(&self, z: f32) -> bool {
+        let (near, far) = self.depth_range();
+        (near..=far).contains(&z)
+    }
+
+    // Project a model's colored edges onto a canvas of the given size, clipped
+    // to the canvas. Used for vector output rather than terminal cells.
+    pub fn project_model(&self, model: &model::Model, width: f32, height: f32) -> Vec<ProjectedEdge> {
+        let mut projected = Vec::new();
+
+        for edge in model.colored_edges.iter() {
+            let camera_start = self.world_to_camera(&model.model_to_world(&edge.start));
+            let camera_end = self.world_to_camera(&model.model_to_world(&edge.end));
+            let Some((camera_start, camera_end, start_color, end_color)) =
+                self.clip_depth(camera_start, camera_end, edge.start_color, edge.end_color) else { continue };
+
+            let start = self.camera_to_canvas(&camera_start, width, height);
+            let end = self.camera_to_canvas(&camera_end, width, height);
+            let Some((t0, t1)) = clip_segment(start, end, (0., 0., width, height)) else { continue };
+
+            let at = |t: f32| (start.0 + t * (end.0 - start.0), start.1 + t * (end.1 - start.1));
+            projected.push(ProjectedEdge {
+                start: at(t0),
+                end: at(t1),
+                start_color: start_color.lerp(end_color, t0),
+                end_color: start_color.lerp(end_color, t1),
+                depth: (camera_start.z + camera_end.z) / 2.,
+                dashed: edge.dashed,
+            });
+        }
+
+        projected
+    }
+
+    // Distance from `center` at which every point projects inside the middle
+    // `fill` fraction of a canvas of the given size, looking along this
+    // camera's orientation. Each point needs |x| / (distance + z) within the
+    // half-angle of the fov across the canvas width and its height, so the
+    // answer is the largest such bound.
+    pub fn fit_distance(&self, points: &[Point], center: &Point, width: f32, height: f32, fill: f32) -> f32 {
+        let origin = self.world_to_camera(center);
+        let half_width = (self.viewport_fov / 2.).tan() * fill;
+        let half_height = half_width * height / width;
+
+        points.iter().fold(0.0f32, |distance, point| {
+            let camera = self.world_to_camera(point);
+            let (x, y, z) = (camera.x - origin.x, camera.y - origin.y, camera.z - origin.z);
+            distance
+                .max(x.abs() / half_width - z)
+                .max(y.abs() / half_height - z)
+                .max(self.viewport_distance - z)
+        })
     }
 
     // Plot points of a given model.
@@ -123,6 +270,7 @@ impl Camera {
     // Plot colored edges of a given model.
     pub fn plot_model_colored_edges(&mut self, model: &model::Model) {
         for edge in model.colored_edges.iter() {
+            self.screen.dashed = edge.dashed;
             self.edge_color(
                 &model.model_to_world(&edge.start),
                 &model.model_to_world(&edge.end),
@@ -130,13 +278,68 @@ impl Camera {
                 edge.end_color
             );
         }
+        self.screen.dashed = false;
+    }
+
+    // Find the residue CA of any of the models whose projection lies nearest
+    // to a screen point, within `radius` screen pixels, so distances run
+    // between residues rather than points on a cartoon's surface. Models
+    // without residues (e.g. XYZ files) offer their atoms instead. Returns
+    // its world coordinates.
+    pub fn pick_point(&self, models: &[model::Model], target: &screen::Point, radius: f32) -> Option<Point> {
+        let points = models.iter().flat_map(|m| {
+            let candidates = if m.residues.is_empty() { &m.points } else { &m.residues };
+            candidates.iter().map(move |p| (m.model_to_world(p), ()))
+        });
+        self.nearest_projection(points, target, radius).map(|(point, _)| point)
+    }
+
+    // The chain and residue number of the drawn edge end nearest to a screen
+    // point, within `radius` screen pixels, for ends that carry a residue.
+    pub fn pick_residue<'a>(&self, models: &'a [model::Model], target: &screen::Point, radius: f32) -> Option<(Option<&'a str>, i32)> {
+        let ends = models.iter().flat_map(|m| m.colored_edges.iter().flat_map(move |edge| {
+            let chain = edge.chain_id.as_deref();
+            [(edge.start, edge.start_resi), (edge.end, edge.end_resi)]
+                .into_iter()
+                .filter_map(move |(point, resi)| Some((m.model_to_world(&point), (chain, resi?))))
+        }));
+        self.nearest_projection(ends, target, radius).map(|(_, residue)| residue)
+    }
+
+    // The candidate whose projection lies nearest to a screen point, within
+    // `radius` screen pixels, among those in the drawn depth range.
+    fn nearest_projection<T>(&self, candidates: impl Iterator<Item = (Point, T)>, target: &screen::Point, radius: f32) -> Option<(Point, T)> {
+        let mut best: Option<(f32, Point, T)> = None;
+
+        for (world_point, item) in candidates {
+            let camera_point = self.world_to_camera(&world_point);
+            if !self.in_depth_range(camera_point.z) { continue; }
+
+            let (screen_x, screen_y) = self.camera_to_screen_exact(&camera_point);
+            let distance = ((screen_x - target.x as f32).powi(2) + (screen_y - target.y as f32).powi(2)).sqrt();
+            if distance > radius { continue; }
+
+            // Prefer the closest projection, breaking near-ties by depth.
+            let score = distance + camera_point.z * 1e-3;
+            if best.as_ref().is_none_or(|(best_score, ..)| score < *best_score) {
+                best = Some((score, world_point, item));
+            }
+        }
+
+        best.map(|(_, point, item)| (point, item))
+    }
+
+    // Screen pixel a world point projects to, if within the drawn depth range.
+    pub fn project(&self, point: &Point) -> Option<screen::Point> {
+        let camera_point = self.world_to_camera(point);
+        self.in_depth_range(camera_point.z).then(|| self.camera_to_screen(&camera_point))
     }
 
     // Plot a 3d point.
     pub fn write(&mut self, val: bool, point: &Point) {
         let camera_point = self.world_to_camera(point);
-        if camera_point.z >= self.viewport_distance {
-            self.screen.write(val, &self.camera_to_screen(&camera_point));
+        if self.in_depth_range(camera_point.z) {
+            self.screen.write(val, &self.camera_to_screen(&camera_point), camera_point.z);
         }
     }
 
@@ -145,88 +348,83 @@ impl Camera {
         self.edge_color(start, end, Rgb::white(), Rgb::white());
     }
 
-    // Check if a point in camera space is within the view frustum (with margin)
-    #[inline]
-    fn is_in_frustum(&self, camera_point: &Point) -> bool {
-        if camera_point.z < self.viewport_distance {
-            return false;
-        }
-        // Calculate frustum bounds at this depth with some margin
-        let half_width = camera_point.z * (self.viewport_fov / 2.0).tan() * 1.5;
-        let aspect = self.screen.height as f32 / self.screen.width as f32;
-        let half_height = half_width * aspect;
-
-        camera_point.x.abs() <= half_width && camera_point.y.abs() <= half_height
-    }
-
     // Plot a 3d edge with color (handles clipping and color interpolation)
     pub fn edge_color(&mut self, start: &Point, end: &Point, start_color: Rgb, end_color: Rgb) {
         let camera_start = self.world_to_camera(start);
         let camera_end = self.world_to_camera(end);
-        let clip_start = camera_start.z < self.viewport_distance;
-        let clip_end = camera_end.z < self.viewport_distance;
-
-        if clip_start && clip_end { return; }
-
-        // No clipping needed - check frustum and draw
-        if !clip_start && !clip_end {
-            // Frustum culling: skip if both points are outside on the same side
-            if !self.is_in_frustum(&camera_start) && !self.is_in_frustum(&camera_end) {
-                let both_left = camera_start.x < 0.0 && camera_end.x < 0.0;
-                let both_right = camera_start.x > 0.0 && camera_end.x > 0.0;
-                let both_up = camera_start.y > 0.0 && camera_end.y > 0.0;
-                let both_down = camera_start.y < 0.0 && camera_end.y < 0.0;
-
-                if both_left || both_right || both_up || both_down {
-                    let z_min = camera_start.z.min(camera_end.z);
-                    let half_width = z_min * (self.viewport_fov / 2.0).tan() * 1.5;
-                    let aspect = self.screen.height as f32 / self.screen.width as f32;
-                    let half_height = half_width * aspect;
-
-                    if (both_left && camera_start.x < -half_width && camera_end.x < -half_width) ||
-                       (both_right && camera_start.x > half_width && camera_end.x > half_width) ||
-                       (both_up && camera_start.y > half_height && camera_end.y > half_height) ||
-                       (both_down && camera_start.y < -half_height && camera_end.y < -half_height) {
-                        return;
-                    }
-                }
-            }
-            self.screen.line_color(
-                &self.camera_to_screen(&camera_start),
-                &self.camera_to_screen(&camera_end),
-                start_color, end_color
+        let Some((camera_start, camera_end, start_color, end_color)) =
+            self.clip_depth(camera_start, camera_end, start_color, end_color) else { return };
+
+        // Clip the projected segment against the screen rectangle, so any edge
+        // with a visible span is drawn even if both endpoints are off screen.
+        let (x0, y0) = self.camera_to_screen_exact(&camera_start);
+        let (x1, y1) = self.camera_to_screen_exact(&camera_end);
+        let bounds = (-0.5, -0.5, self.screen.width as f32 - 0.5, self.screen.height as f32 - 0.5);
+
+        if let Some((t0, t1)) = clip_segment((x0, y0), (x1, y1), bounds) {
+            let depth = (
+                screen::interpolate_depth(camera_start.z, camera_end.z, t0),
+                screen::interpolate_depth(camera_start.z, camera_end.z, t1),
             );
+
+            let at = |t: f32| (x0 + t * (x1 - x0), y0 + t * (y1 - y0));
+            let (start, end) = (at(t0), at(t1));
+            let (start_color, end_color) = (start_color.lerp(end_color, t0), start_color.lerp(end_color, t1));
+
+            // Side strands first, so the center line wins where they overlap.
+            let mean_depth = (camera_start.z + camera_end.z) / 2.;
+            for ((dx, dy), shade) in self.ribbon_strands(start, end, mean_depth, self.screen.width as f32) {
+                self.draw_segment(
+                    (start.0 + dx, start.1 + dy),
+                    (end.0 + dx, end.1 + dy),
+                    start_color.scale(shade),
+                    end_color.scale(shade),
+                    depth
+                );
+            }
+            self.draw_segment(start, end, start_color, end_color, depth);
+        }
+    }
+
+    // Draw a segment between unrounded screen coordinates, antialiased if enabled.
+    fn draw_segment(&mut self, start: (f32, f32), end: (f32, f32), start_color: Rgb, end_color: Rgb, depth: (f32, f32)) {
+        if self.antialias {
+            self.screen.line_color_aa(start, end, start_color, end_color, depth);
             return;
         }
 
-        // Handle clipping with color interpolation
-        let (clipped, unclipped, clipped_color, unclipped_color) = if clip_start {
-            (camera_start, camera_end, start_color, end_color)
-        } else {
-            (camera_end, camera_start, end_color, start_color)
-        };
-
-        let lambda = (self.viewport_distance - clipped.z) / (unclipped.z - clipped.z);
-        let new_clipped = Point::new(
-            lambda * (unclipped.x - clipped.x) + clipped.x,
-            lambda * (unclipped.y - clipped.y) + clipped.y,
-            self.viewport_distance
-        );
+        let round = |(x, y): (f32, f32)| screen::Point::new(x.round() as i32, y.round() as i32);
+        self.screen.line_color(&round(start), &round(end), start_color, end_color, depth);
+    }
 
-        let clip_color = Rgb::new(
-            ((1.0 - lambda) * clipped_color.r as f32 + lambda * unclipped_color.r as f32) as u8,
-            ((1.0 - lambda) * clipped_color.g as f32 + lambda * unclipped_color.g as f32) as u8,
-            ((1.0 - lambda) * clipped_color.b as f32 + lambda * unclipped_color.b as f32) as u8,
-        );
+    // Screen offsets and shades of the strands drawn beside an edge for a
+    // ribbon, spaced by a fixed world width at the edge's depth so they follow
+    // the zoom. None when ribbons are off, for dashed edges, or when the
+    // strands would merge with the center line.
+    fn ribbon_strands(&self, start: (f32, f32), end: (f32, f32), depth: f32, canvas_width: f32) -> Vec<((f32, f32), f32)> {
+        if !self.ribbon || self.screen.dashed {
+            return Vec::new();
+        }
+        let pixels_per_unit = canvas_width / (2. * (self.viewport_fov / 2.).tan() * depth);
+        let offset = (RIBBON_HALF_WIDTH * pixels_per_unit).min(RIBBON_MAX_OFFSET);
+        let (delta_x, delta_y) = (end.0 - start.0, end.1 - start.1);
+        let length = (delta_x * delta_x + delta_y * delta_y).sqrt();
+        if offset < RIBBON_MIN_OFFSET || length == 0. {
+            return Vec::new();
+        }
 
-        self.screen.line_color(
-            &self.camera_to_screen(&new_clipped),
-            &self.camera_to_screen(&unclipped),
-            clip_color, unclipped_color
-        );
+        // Normal pointing up the screen, so every edge is lit from the same side.
+        let (mut normal_x, mut normal_y) = (-delta_y / length, delta_x / length);
+        if normal_y > 0. || (normal_y == 0. && normal_x > 0.) {
+            (normal_x, normal_y) = (-normal_x, -normal_y);
+        }
+        RIBBON_STRANDS.iter()
+            .map(|&(side, shade)| ((normal_x * side * offset, normal_y * side * offset), shade))
+            .collect()
     }
 
     // Plot a model into a specific viewport section of the screen.
+    #[allow(clippy::too_many_arguments)]
     pub fn plot_model_in_viewport(
         &mut self,
         model: &model::Model,
@@ -234,6 +432,7 @@ impl Camera {
         yaw: f32,
         pitch: f32,
         viewport_x_offset: u16,
+        viewport_y_offset: u16,
         viewport_width: u16,
         viewport_height: u16,
     ) {
@@ -249,8 +448,8 @@ impl Camera {
         let aspect = viewport_height as f32 / viewport_width as f32;
         let clip_x_min = viewport_x_offset as i32;
         let clip_x_max = (viewport_x_offset + viewport_width) as i32;
-        let clip_y_min = 0;
-        let clip_y_max = viewport_height as i32;
+        let clip_y_min = viewport_y_offset as i32;
+        let clip_y_max = (viewport_y_offset + viewport_height) as i32;
 
         for edge in model.colored_edges.iter() {
             let start = model.model_to_world(&edge.start);
@@ -258,49 +457,58 @@ impl Camera {
 
             let camera_start = self.world_to_camera(&start);
             let camera_end = self.world_to_camera(&end);
+            let Some((camera_start, camera_end, start_color, end_color)) =
+                self.clip_depth(camera_start, camera_end, edge.start_color, edge.end_color) else { continue };
+            self.screen.dashed = edge.dashed;
+
+            if self.antialias {
+                let offset = |(x, y): (f32, f32)| (x + clip_x_min as f32, y + clip_y_min as f32);
+                let start = offset(self.camera_to_canvas(&camera_start, viewport_width as f32, viewport_height as f32));
+                let end = offset(self.camera_to_canvas(&camera_end, viewport_width as f32, viewport_height as f32));
+                let depth = (camera_start.z, camera_end.z);
+                // Both pixels a sample covers must stay inside the viewport.
+                let bounds = (clip_x_min as f32, clip_y_min as f32, (clip_x_max - 1) as f32, (clip_y_max - 1) as f32);
+                let mean_depth = (camera_start.z + camera_end.z) / 2.;
+                for ((dx, dy), shade) in self.ribbon_strands(start, end, mean_depth, viewport_width as f32) {
+                    self.line_aa_clipped(
+                        (start.0 + dx, start.1 + dy),
+                        (end.0 + dx, end.1 + dy),
+                        start_color.scale(shade),
+                        end_color.scale(shade),
+                        depth,
+                        bounds,
+                    );
+                }
+                self.line_aa_clipped(start, end, start_color, end_color, depth, bounds);
+                continue;
+            }
 
-            let clip_start = camera_start.z < self.viewport_distance;
-            let clip_end = camera_end.z < self.viewport_distance;
-
-            if clip_start && clip_end { continue; }
+            let screen_start = self.camera_to_viewport_screen(&camera_start, viewport_width, viewport_height, aspect);
+            let screen_end = self.camera_to_viewport_screen(&camera_end, viewport_width, viewport_height, aspect);
+            let depth = (camera_start.z, camera_end.z);
 
-            let (screen_start, screen_end, start_color, end_color) = if !clip_start && !clip_end {
-                let s = self.camera_to_viewport_screen(&camera_start, viewport_width, viewport_height, aspect);
-                let e = self.camera_to_viewport_screen(&camera_end, viewport_width, viewport_height, aspect);
-                (s, e, edge.start_color, edge.end_color)
-            } else {
-                let (clipped, unclipped, clipped_color, unclipped_color) = if clip_start {
-                    (camera_start, camera_end, edge.start_color, edge.end_color)
-                } else {
-                    (camera_end, camera_start, edge.end_color, edge.start_color)
-                };
-
-                let lambda = (self.viewport_distance - clipped.z) / (unclipped.z - clipped.z);
-                let new_clipped = Point::new(
-                    lambda * (unclipped.x - clipped.x) + clipped.x,
-                    lambda * (unclipped.y - clipped.y) + clipped.y,
-                    self.viewport_distance
-                );
+            let offset_start = screen::Point::new(screen_start.x + clip_x_min, screen_start.y + clip_y_min);
+            let offset_end = screen::Point::new(screen_end.x + clip_x_min, screen_end.y + clip_y_min);
 
-                let clip_color = Rgb::new(
-                    ((1.0 - lambda) * clipped_color.r as f32 + lambda * unclipped_color.r as f32) as u8,
-                    ((1.0 - lambda) * clipped_color.g as f32 + lambda * unclipped_color.g as f32) as u8,
-                    ((1.0 - lambda) * clipped_color.b as f32 + lambda * unclipped_color.b as f32) as u8,
+            let strands = self.ribbon_strands(
+                (offset_start.x as f32, offset_start.y as f32),
+                (offset_end.x as f32, offset_end.y as f32),
+                (camera_start.z + camera_end.z) / 2.,
+                viewport_width as f32,
+            );
+            for ((dx, dy), shade) in strands {
+                let shift = |point: &screen::Point| screen::Point::new(point.x + dx.round() as i32, point.y + dy.round() as i32);
+                self.screen.line_color_clipped(
+                    &shift(&offset_start), &shift(&offset_end), start_color.scale(shade), end_color.scale(shade), depth,
+                    clip_x_min, clip_x_max, clip_y_min, clip_y_max
                 );
-
-                let s = self.camera_to_viewport_screen(&new_clipped, viewport_width, viewport_height, aspect);
-                let e = self.camera_to_viewport_screen(&unclipped, viewport_width, viewport_height, aspect);
-                (s, e, clip_color, unclipped_color)
-            };
-
-            let offset_start = screen::Point::new(screen_start.x + viewport_x_offset as i32, screen_start.y);
-            let offset_end = screen::Point::new(screen_end.x + viewport_x_offset as i32, screen_end.y);
-
+            }
             self.screen.line_color_clipped(
-                &offset_start, &offset_end, start_color, end_color,
+                &offset_start, &offset_end, start_color, end_color, depth,
                 clip_x_min, clip_x_max, clip_y_min, clip_y_max
             );
         }
+        self.screen.dashed = false;
 
         // Restore original camera parameters
         self.coordinates = orig_coords;
@@ -308,6 +516,20 @@ impl Camera {
         self.pitch = orig_pitch;
     }
 
+    // Draw the part of an antialiased segment within (x_min, y_min, x_max,
+    // y_max), so a viewport's lines never spill into its neighbors.
+    fn line_aa_clipped(&mut self, start: (f32, f32), end: (f32, f32), start_color: Rgb, end_color: Rgb, depth: (f32, f32), bounds: (f32, f32, f32, f32)) {
+        let Some((t0, t1)) = clip_segment(start, end, bounds) else { return };
+        let at = |t: f32| (start.0 + t * (end.0 - start.0), start.1 + t * (end.1 - start.1));
+        self.screen.line_color_aa(
+            at(t0),
+            at(t1),
+            start_color.lerp(end_color, t0),
+            start_color.lerp(end_color, t1),
+            (screen::interpolate_depth(depth.0, depth.1, t0), screen::interpolate_depth(depth.0, depth.1, t1)),
+        );
+    }
+
     // Convert camera to screen coordinates for a specific viewport
     fn camera_to_viewport_screen(&self, point: &Point, viewport_width: u16, viewport_height: u16, aspect: f32) -> screen::Point {
         let viewport_x = point.x * self.viewport_distance / point.z;
@@ -322,3 +544,58 @@ impl Camera {
         screen::Point::new(screen_x.round() as i32, screen_y.round() as i32)
     }
 }
+
+// Liang–Barsky clip of a 2d segment against (x_min, y_min, x_max, y_max).
+// Returns the visible parameter range along the segment, if any.
+fn clip_segment(start: (f32, f32), end: (f32, f32), bounds: (f32, f32, f32, f32)) -> Option<(f32, f32)> {
+    let (x_min, y_min, x_max, y_max) = bounds;
+    let delta_x = end.0 - start.0;
+    let delta_y = end.1 - start.1;
+
+    let mut t0: f32 = 0.;
+    let mut t1: f32 = 1.;
+
+    for (p, q) in [
+        (-delta_x, start.0 - x_min),
+        (delta_x, x_max - start.0),
+        (-delta_y, start.1 - y_min),
+        (delta_y, y_max - start.1),
+    ] {
+        if p == 0. {
+            // Parallel to this boundary; reject if outside it.
+            if q < 0. { return None; }
+        } else {
+            let r = q / p;
+            if p < 0. {
+                t0 = t0.max(r);
+            } else {
+                t1 = t1.min(r);
+            }
+        }
+    }
+
+    if t0 > t1 || !t0.is_finite() || !t1.is_finite() {
+        return None;
+    }
+    Some((t0, t1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clip_segment_keeps_diagonal_crossing() {
+        // Both ends lie outside the bounds, but the segment cuts across them.
+        let (start, end) = ((-5., -3.), (15., 13.));
+        let bounds = (0., 0., 10., 10.);
+        let (t0, t1) = clip_segment(start, end, bounds).expect("segment crosses the bounds");
+        assert!(t0 < t1);
+        for t in [t0, t1] {
+            let x = start.0 + (end.0 - start.0) * t;
+            let y = start.1 + (end.1 - start.1) * t;
+            assert!((bounds.0 - 1e-4..=bounds.2 + 1e-4).contains(&x), "x = {x}");
+            assert!((bounds.1 - 1e-4..=bounds.3 + 1e-4).contains(&y), "y = {y}");
+        }
+    }
+}